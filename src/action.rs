@@ -0,0 +1,205 @@
+use specs::prelude::*;
+use std::fmt::{self, Display, Formatter};
+
+use ecs::*;
+use replay;
+use time::Time;
+use undo::{self, Snapshot};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameAction {
+    Build(usize, usize, Structure),
+    PickUp(usize, usize),
+    Place(usize, usize),
+    SkipTurn,
+}
+
+impl GameAction {
+    pub fn target(&self) -> Option<(usize, usize)> {
+        match *self {
+            GameAction::Build(x, y, _) | GameAction::PickUp(x, y) | GameAction::Place(x, y) => {
+                Some((x, y))
+            }
+            GameAction::SkipTurn => None,
+        }
+    }
+
+    pub fn passes_turn(&self) -> bool {
+        match *self {
+            GameAction::PickUp(..) => false,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlacementError {
+    NoTile,
+    FarFromCity,
+    Underwater,
+    NeedsShore,
+    Occupied,
+    TooHigh,
+    TooLow,
+    AlreadyHolding,
+    NothingHeld,
+}
+
+impl Display for PlacementError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PlacementError::NoTile => "Nothing here!",
+                PlacementError::FarFromCity => "Too far from city!",
+                PlacementError::Underwater => "Can't build on water!",
+                PlacementError::NeedsShore => "Needs to be on shore!",
+                PlacementError::Occupied => "Already built on!",
+                PlacementError::TooHigh => "Too high to build on!",
+                PlacementError::TooLow => "Nothing left to dig up!",
+                PlacementError::AlreadyHolding => "Already holding a tile!",
+                PlacementError::NothingHeld => "Not holding a tile!",
+            }
+        )
+    }
+}
+
+pub fn terraform(world: &World, x: usize, y: usize) -> GameAction {
+    match world.read_resource::<Grid>().held_tile {
+        Some(_) => GameAction::Place(x, y),
+        None => GameAction::PickUp(x, y),
+    }
+}
+
+pub fn check(world: &World, action: &GameAction) -> Result<(), Vec<PlacementError>> {
+    let (x, y) = match action.target() {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+    let (pos, tile) = match construction::top_tile(world, x, y) {
+        Some((_, pos, tile)) => (pos, tile),
+        None => return Err(vec![PlacementError::NoTile]),
+    };
+    let grid = world.read_resource::<Grid>();
+    let mut errors = Vec::new();
+    if !grid.is_civilizable(x, y) {
+        errors.push(PlacementError::FarFromCity);
+    }
+    if tile == Tile::Water {
+        errors.push(PlacementError::Underwater);
+    }
+    let occupied = match tile {
+        Tile::Structure(_) => true,
+        _ => false,
+    };
+    let stacks = tile == Tile::Terrain;
+    match *action {
+        GameAction::Build(_, _, structure) => {
+            if occupied {
+                errors.push(PlacementError::Occupied);
+            }
+            if stacks && pos.z() + 1 >= grid.dimensions().2 {
+                errors.push(PlacementError::TooHigh);
+            }
+            let requires_shore = world
+                .read_resource::<StructureDefs>()
+                .get(structure)
+                .requires_shore;
+            if requires_shore && !construction::on_shore(
+                &grid,
+                &world.read_storage::<Position>(),
+                &world.read_storage::<Tile>(),
+                &pos,
+            ) {
+                errors.push(PlacementError::NeedsShore);
+            }
+        }
+        GameAction::PickUp(..) => {
+            if grid.held_tile.is_some() {
+                errors.push(PlacementError::AlreadyHolding);
+            }
+            if pos.z() == 0 || grid.current_sealevel == 0 {
+                errors.push(PlacementError::TooLow);
+            }
+        }
+        GameAction::Place(..) => {
+            if grid.held_tile.is_none() {
+                errors.push(PlacementError::NothingHeld);
+            }
+            if occupied {
+                errors.push(PlacementError::Occupied);
+            }
+            if stacks && pos.z() + 1 >= grid.dimensions().2 {
+                errors.push(PlacementError::TooHigh);
+            }
+        }
+        GameAction::SkipTurn => (),
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn validate(world: &World, action: &GameAction) -> bool {
+    check(world, action).is_ok()
+}
+
+pub fn apply(world: &mut World, action: &GameAction) -> bool {
+    if !validate(world, action) {
+        return false;
+    }
+    let snapshot = Snapshot::take(world);
+    if !execute(world, action) {
+        return false;
+    }
+    undo::push(world, snapshot);
+    replay::record(world, *action);
+    true
+}
+
+pub fn perform(world: &mut World, action: &GameAction) -> bool {
+    validate(world, action) && execute(world, action)
+}
+
+fn execute(world: &mut World, action: &GameAction) -> bool {
+    let applied = match *action {
+        GameAction::Build(x, y, structure) => match construction::top_tile(world, x, y) {
+            Some((entity, pos, _)) => {
+                construction::build(world, entity, &pos, Tile::Structure(structure))
+            }
+            None => false,
+        },
+        GameAction::PickUp(x, y) => match construction::top_tile(world, x, y) {
+            Some((entity, pos, tile)) => {
+                construction::pick_up(world, entity, &pos, tile);
+                true
+            }
+            None => false,
+        },
+        GameAction::Place(x, y) => {
+            let held_tile = world.read_resource::<Grid>().held_tile;
+            match (construction::top_tile(world, x, y), held_tile) {
+                (Some((entity, pos, _)), Some(held_tile)) => {
+                    if construction::build(world, entity, &pos, held_tile) {
+                        world.write_resource::<Grid>().held_tile = None;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                _ => false,
+            }
+        }
+        GameAction::SkipTurn => true,
+    };
+    if applied {
+        debug!("Applied {:?}", action);
+        if action.passes_turn() {
+            world.write_resource::<Time>().turn_passed = true;
+        }
+    }
+    applied
+}
@@ -14,7 +14,7 @@ pub enum MeshHandle {
     TileSelector,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum SpriteHandle {
     TileSprite,
     Terraform,
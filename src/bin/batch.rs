@@ -0,0 +1,38 @@
+extern crate its_not_cool;
+
+use std::env;
+
+use its_not_cool::bot::{self, DEFAULT_MAX_TURNS};
+use its_not_cool::ecs::MapSettings;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let games = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(20u32);
+    let max_turns = args
+        .next()
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(DEFAULT_MAX_TURNS);
+    let seeds = (1..games + 1).collect::<Vec<_>>();
+    let count = bot::reference_strategies(0).len();
+
+    println!(
+        "{:<16}{:>8}{:>12}{:>12}{:>12}",
+        "strategy", "games", "avg score", "avg turns", "survived"
+    );
+    for index in 0..count {
+        let report = bot::run_batch(
+            |seed| bot::reference_strategies(seed).remove(index),
+            MapSettings::default(),
+            &seeds,
+            max_turns,
+        );
+        println!(
+            "{:<16}{:>8}{:>12.1}{:>12.1}{:>12}",
+            report.strategy,
+            report.results.len(),
+            report.average_score(),
+            report.average_turns(),
+            report.survivors()
+        );
+    }
+}
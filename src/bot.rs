@@ -0,0 +1,286 @@
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+use specs::prelude::*;
+
+use action::{self, GameAction};
+use ecs::*;
+use headless::Simulation;
+use time::Time;
+
+pub const DEFAULT_MAX_TURNS: i32 = 200;
+
+pub trait Strategy {
+    fn name(&self) -> &str;
+
+    fn next_action(&mut self, world: &World) -> GameAction;
+}
+
+pub fn candidates(world: &World) -> Vec<(usize, usize)> {
+    let grid = world.read_resource::<Grid>();
+    let positions = world.read_storage::<Position>();
+    let tiles = world.read_storage::<Tile>();
+    let mut found = (&positions, &tiles)
+        .join()
+        .filter(|(pos, tile)| {
+            (**tile == Tile::Terrain || **tile == Tile::Trees)
+                && grid.is_top_tile(pos)
+                && grid.is_civilizable(pos.x(), pos.y())
+        })
+        .map(|(pos, _)| (pos.x(), pos.y()))
+        .collect::<Vec<_>>();
+    found.sort_by_key(|&(x, y)| (y, x));
+    found
+}
+
+fn placement(
+    world: &World,
+    structure: Structure,
+    candidates: &[(usize, usize)],
+) -> Option<GameAction> {
+    candidates
+        .iter()
+        .map(|&(x, y)| GameAction::Build(x, y, structure))
+        .find(|action| action::validate(world, action))
+}
+
+pub struct RandomStrategy {
+    rng: XorShiftRng,
+}
+
+impl RandomStrategy {
+    pub fn new(seed: u32) -> RandomStrategy {
+        let mut bytes = [0u8; 16];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (seed.rotate_left(i as u32 * 8) & 0xff) as u8 ^ 0x5a;
+        }
+        RandomStrategy {
+            rng: XorShiftRng::from_seed(bytes),
+        }
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn next_action(&mut self, world: &World) -> GameAction {
+        let candidates = candidates(world);
+        let roll = self.rng.gen_range(0, 10);
+        if candidates.is_empty() || roll == 0 {
+            return GameAction::SkipTurn;
+        }
+        let structures = world
+            .read_resource::<StructureDefs>()
+            .iter()
+            .map(|def| def.structure)
+            .collect::<Vec<_>>();
+        let (x, y) = candidates[self.rng.gen_range(0, candidates.len())];
+        let holding = world.read_resource::<Grid>().held_tile.is_some();
+        let action = if holding || roll == 1 {
+            action::terraform(world, x, y)
+        } else {
+            GameAction::Build(x, y, structures[self.rng.gen_range(0, structures.len())])
+        };
+        if action::validate(world, &action) {
+            action
+        } else {
+            GameAction::SkipTurn
+        }
+    }
+}
+
+pub struct GreedyStrategy {
+    name: &'static str,
+    value: fn(&StructureDef, &Time) -> i32,
+}
+
+impl GreedyStrategy {
+    pub fn food() -> GreedyStrategy {
+        GreedyStrategy {
+            name: "greedy food",
+            value: |def, time| {
+                let housing = if time.homeless > 0 { def.housing } else { 0 };
+                2 * def.food + housing
+            },
+        }
+    }
+
+    pub fn nature() -> GreedyStrategy {
+        GreedyStrategy {
+            name: "greedy nature",
+            value: |def, time| {
+                let powered = if time.power + def.power >= 0 {
+                    def.nature_if_powered
+                } else {
+                    0
+                };
+                let power = if time.power < 0 { def.power } else { 0 };
+                2 * (def.nature + powered) + power
+            },
+        }
+    }
+}
+
+impl Strategy for GreedyStrategy {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn next_action(&mut self, world: &World) -> GameAction {
+        let candidates = candidates(world);
+        let defs = world.read_resource::<StructureDefs>();
+        let time = world.read_resource::<Time>();
+        let mut ranked = defs
+            .iter()
+            .map(|def| ((self.value)(def, &time), def.structure))
+            .filter(|(value, _)| *value > 0)
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.0.cmp(&a.0));
+        ranked
+            .iter()
+            .filter_map(|(_, structure)| placement(world, *structure, &candidates))
+            .next()
+            .unwrap_or(GameAction::SkipTurn)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameReport {
+    pub seed: u32,
+    pub score: i32,
+    pub turns: i32,
+    pub survived: bool,
+}
+
+pub fn play(
+    strategy: &mut Strategy,
+    settings: MapSettings,
+    seed: u32,
+    max_turns: i32,
+) -> GameReport {
+    let mut simulation = Simulation::new(settings, seed);
+    while !simulation.is_over() && simulation.world().read_resource::<Time>().turn < max_turns {
+        let action = strategy.next_action(simulation.world());
+        if !simulation.step(&action) {
+            simulation.step(&GameAction::SkipTurn);
+        }
+    }
+    let time = simulation.world().read_resource::<Time>();
+    GameReport {
+        seed,
+        score: time.score,
+        turns: time.turn,
+        survived: !time.game_over,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchReport {
+    pub strategy: String,
+    pub results: Vec<GameReport>,
+}
+
+impl BatchReport {
+    pub fn average_score(&self) -> f64 {
+        self.average(|result| result.score)
+    }
+
+    pub fn average_turns(&self) -> f64 {
+        self.average(|result| result.turns)
+    }
+
+    pub fn survivors(&self) -> usize {
+        self.results.iter().filter(|result| result.survived).count()
+    }
+
+    fn average<F>(&self, value: F) -> f64
+    where
+        F: Fn(&GameReport) -> i32,
+    {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        self.results.iter().map(|result| value(result) as f64).sum::<f64>()
+            / self.results.len() as f64
+    }
+}
+
+pub fn run_batch<F>(
+    mut strategy: F,
+    settings: MapSettings,
+    seeds: &[u32],
+    max_turns: i32,
+) -> BatchReport
+where
+    F: FnMut(u32) -> Box<Strategy>,
+{
+    let mut name = String::new();
+    let results = seeds
+        .iter()
+        .map(|seed| {
+            let mut player = strategy(*seed);
+            name = player.name().to_owned();
+            play(&mut *player, settings, *seed, max_turns)
+        })
+        .collect();
+    BatchReport {
+        strategy: name,
+        results,
+    }
+}
+
+pub fn reference_strategies(seed: u32) -> Vec<Box<Strategy>> {
+    vec![
+        Box::new(RandomStrategy::new(seed)) as Box<Strategy>,
+        Box::new(GreedyStrategy::food()) as Box<Strategy>,
+        Box::new(GreedyStrategy::nature()) as Box<Strategy>,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: [u32; 3] = [1, 2, 3];
+    const MAX_TURNS: i32 = 30;
+
+    #[test]
+    fn batches_are_reproducible() {
+        for index in 0..reference_strategies(0).len() {
+            let batch = || {
+                run_batch(
+                    |seed| reference_strategies(seed).remove(index),
+                    MapSettings::default(),
+                    &SEEDS,
+                    MAX_TURNS,
+                )
+            };
+            let report = batch();
+            assert_eq!(report.results.len(), SEEDS.len());
+            assert!(report.results.iter().all(|result| result.turns <= MAX_TURNS));
+            assert_eq!(report, batch());
+        }
+    }
+
+    #[test]
+    fn random_strategy_terraforms() {
+        let mut strategy = RandomStrategy::new(5);
+        let mut simulation = Simulation::new(MapSettings::default(), 5);
+        let mut terraformed = false;
+        for _ in 0..200 {
+            if simulation.is_over() {
+                break;
+            }
+            let action = strategy.next_action(simulation.world());
+            if !simulation.step(&action) {
+                simulation.step(&GameAction::SkipTurn);
+            }
+            terraformed |= match action {
+                GameAction::PickUp(..) | GameAction::Place(..) => true,
+                _ => false,
+            };
+        }
+        assert!(terraformed);
+    }
+}
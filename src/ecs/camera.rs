@@ -0,0 +1,101 @@
+use nalgebra as na;
+
+use super::Position;
+
+pub const PAN_STEP: f32 = 32.0;
+const ZOOM_STEP: f32 = 1.25;
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 3.0;
+
+fn center() -> na::Point2<f32> {
+    na::Point2::new(320.0, 190.0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub pan: na::Vector2<f32>,
+    pub zoom: f32,
+    pub rotation: usize,
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera {
+            pan: na::Vector2::new(0.0, 0.0),
+            zoom: 1.0,
+            rotation: 0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn to_screen(&self, point: na::Point2<f32>) -> na::Point2<f32> {
+        center() + (point - center() + self.pan) * self.zoom
+    }
+
+    pub fn to_map(&self, point: na::Point2<f32>) -> na::Point2<f32> {
+        center() + (point - center()) / self.zoom - self.pan
+    }
+
+    pub fn scale(&self) -> na::Vector2<f32> {
+        na::Vector2::new(self.zoom, self.zoom)
+    }
+
+    pub fn pan_by(&mut self, delta: na::Vector2<f32>) {
+        self.pan += delta / self.zoom;
+    }
+
+    pub fn zoom_by(&mut self, steps: i32, anchor: na::Point2<f32>) {
+        let fixed = self.to_map(anchor);
+        self.zoom = (self.zoom * ZOOM_STEP.powi(steps)).max(MIN_ZOOM).min(MAX_ZOOM);
+        self.pan = (anchor - center()) / self.zoom - (fixed - center());
+    }
+
+    pub fn zoom_at_center(&mut self, steps: i32) {
+        self.zoom_by(steps, center());
+    }
+
+    pub fn rotate_by(&mut self, steps: i32) {
+        self.rotation = ((self.rotation as i32 + steps % 4 + 4) % 4) as usize;
+    }
+
+    pub fn view_dimensions(&self, (w, h, d): (usize, usize, usize)) -> (usize, usize, usize) {
+        if self.rotation % 2 == 0 {
+            (w, h, d)
+        } else {
+            (h, w, d)
+        }
+    }
+
+    pub fn view_coords(
+        &self,
+        (x, y): (usize, usize),
+        (w, h, _): (usize, usize, usize),
+    ) -> (usize, usize) {
+        match self.rotation {
+            1 => (y, w - 1 - x),
+            2 => (w - 1 - x, h - 1 - y),
+            3 => (h - 1 - y, x),
+            _ => (x, y),
+        }
+    }
+
+    pub fn grid_coords(
+        &self,
+        (x, y): (usize, usize),
+        (w, h, _): (usize, usize, usize),
+    ) -> (usize, usize) {
+        match self.rotation {
+            1 => (w - 1 - y, x),
+            2 => (w - 1 - x, h - 1 - y),
+            3 => (y, h - 1 - x),
+            _ => (x, y),
+        }
+    }
+
+    pub fn draw_order(&self, pos: &Position, dimensions: (usize, usize, usize)) -> i32 {
+        let (x, y) = self.view_coords((pos.x(), pos.y()), dimensions);
+        let (w, h, _) = self.view_dimensions(dimensions);
+        (x as i32) - (y as i32) * (w as i32) + (pos.z() as i32) * (w as i32) * (h as i32 + 1)
+    }
+}
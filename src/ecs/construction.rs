@@ -0,0 +1,113 @@
+use super::*;
+use history::History;
+
+pub fn build(world: &World, target: Entity, target_pos: &Position, new_tile: Tile) -> bool {
+    let built = place(world, target, target_pos, new_tile);
+    if built {
+        if let Tile::Structure(_) = new_tile {
+            if let Some(mut history) = world.res.try_fetch_mut::<History>() {
+                history.structures_built += 1;
+            }
+        }
+    }
+    built
+}
+
+fn place(world: &World, target: Entity, target_pos: &Position, new_tile: Tile) -> bool {
+    let mut grid = world.write_resource::<Grid>();
+    let entities = world.entities();
+    let mut positions = world.write_storage::<Position>();
+    let mut tiles = world.write_storage::<Tile>();
+    let current = match tiles.get(target) {
+        Some(tile) => *tile,
+        None => return false,
+    };
+    match current {
+        Tile::Trees => {
+            debug!(
+                "replacing {} {} {}",
+                target_pos.x(),
+                target_pos.y(),
+                target_pos.z()
+            );
+            *tiles.get_mut(target).unwrap() = new_tile;
+            grid.new_position(new_tile, target_pos.x(), target_pos.y(), target_pos.z());
+            true
+        }
+        Tile::Terrain => {
+            let entity = entities.create();
+            positions
+                .insert(
+                    entity,
+                    grid.new_position(
+                        new_tile,
+                        target_pos.x(),
+                        target_pos.y(),
+                        target_pos.z() + 1,
+                    ),
+                )
+                .unwrap();
+            tiles.insert(entity, new_tile).unwrap();
+            true
+        }
+        _ => false,
+    }
+}
+
+pub fn pick_up(world: &mut World, target: Entity, target_pos: &Position, target_tile: Tile) {
+    {
+        let mut grid = world.write_resource::<Grid>();
+        grid.held_tile = Some(target_tile);
+        debug!(
+            "removing {} {} {}",
+            target_pos.x(),
+            target_pos.y(),
+            target_pos.z()
+        );
+        if let Err(e) = world.entities().delete(target) {
+            error!("Could not remove {:?}: {:?}", target, e);
+        }
+        grid.uncivilize(target_pos.x(), target_pos.y());
+        grid.lower_heightmap(target_pos.x(), target_pos.y());
+        grid.new_position(
+            Tile::Terrain,
+            target_pos.x(),
+            target_pos.y(),
+            target_pos.z() - 1,
+        );
+        grid.current_sealevel -= 1;
+    }
+    world.maintain();
+    mapgen::Flood.run_now(&world.res);
+}
+
+pub fn on_shore(
+    grid: &Grid,
+    positions: &ReadStorage<Position>,
+    tiles: &ReadStorage<Tile>,
+    pos: &Position,
+) -> bool {
+    let (w, h, _) = grid.dimensions();
+    (positions, tiles)
+        .join()
+        .filter(|(p, _)| {
+            (p.y() == pos.y()
+                && (p.x() > 0 && p.x() - 1 <= pos.x())
+                && (p.x() < w && p.x() + 1 >= pos.x()))
+                || (p.x() == pos.x()
+                    && (p.y() > 0 && p.y() - 1 <= pos.y())
+                    && (p.y() < h && p.y() + 1 >= pos.y()))
+        })
+        .any(|(_, tile)| *tile == Tile::Water)
+}
+
+pub fn top_tile(world: &World, x: usize, y: usize) -> Option<(Entity, Position, Tile)> {
+    let grid = world.read_resource::<Grid>();
+    let entities = world.entities();
+    let positions = world.read_storage::<Position>();
+    let tiles = world.read_storage::<Tile>();
+    (&*entities, &positions, &tiles)
+        .join()
+        .find(|(_, pos, _)| pos.x() == x && pos.y() == y && grid.is_top_tile(pos))
+        .map(|(entity, pos, tile)| (entity, *pos, *tile))
+}
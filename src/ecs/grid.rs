@@ -1,6 +1,6 @@
 use super::*;
 use std::cmp::{Ord, Ordering};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Position {
@@ -36,11 +36,16 @@ impl PartialOrd for Position {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Grid {
     pub current_sealevel: usize,
     dimensions: (usize, usize, usize),
     map: HashMap<(usize, usize), (usize, bool)>,
     pub held_tile: Option<Tile>,
+    #[serde(default)]
+    center: Option<(usize, usize)>,
+    #[serde(skip)]
+    connected: HashSet<(usize, usize)>,
 }
 
 impl Default for Grid {
@@ -56,6 +61,8 @@ impl Grid {
             dimensions: (width, height, depth),
             map: HashMap::new(),
             held_tile: None,
+            center: None,
+            connected: HashSet::new(),
         }
     }
 
@@ -65,6 +72,7 @@ impl Grid {
         } else {
             false
         };
+        let was_civilized = self.is_civilized(x, y);
         {
             let (height, civ) = self.map.entry((x, y)).or_insert((z, civilized));
             if *height < z {
@@ -72,12 +80,21 @@ impl Grid {
             }
             *civ = civilized;
         }
-        let (w, h, d) = self.dimensions();
+        if civilized && self.center.is_none() {
+            self.center = Some((x, y));
+        } else if !civilized && self.center == Some((x, y)) {
+            self.relocate_center();
+        }
+        if civilized || was_civilized {
+            self.reconnect();
+        }
+        let (w, h, _) = self.dimensions();
         Position {
             x,
             y,
             z,
-            ordering: (x as i32) - (y as i32) * (h as i32).pow(2) + (z as i32) * (d as i32).pow(3),
+            ordering: (x as i32) - (y as i32) * (w as i32)
+                + (z as i32) * (w as i32) * (h as i32 + 1),
         }
     }
 
@@ -91,6 +108,10 @@ impl Grid {
         self.dimensions
     }
 
+    pub fn height(&self, x: usize, y: usize) -> Option<usize> {
+        self.map.get(&(x, y)).map(|(height, _)| *height)
+    }
+
     pub fn is_top_tile(&self, pos: &Position) -> bool {
         if let Some((height, _)) = self.map.get(&(pos.x(), pos.y())) {
             return pos.z() == *height;
@@ -109,27 +130,121 @@ impl Grid {
         if let Some((_, civilized)) = self.map.get_mut(&(x, y)) {
             *civilized = false;
         }
+        if self.center == Some((x, y)) {
+            self.relocate_center();
+        }
+        self.reconnect();
+    }
+
+    pub fn city_center(&self) -> Option<(usize, usize)> {
+        self.center
+    }
+
+    pub fn components(&self) -> Vec<HashSet<(usize, usize)>> {
+        let mut civilized = self
+            .map
+            .iter()
+            .filter(|(_, (_, civilized))| *civilized)
+            .map(|(xy, _)| *xy)
+            .collect::<Vec<_>>();
+        civilized.sort();
+        let mut seen = HashSet::new();
+        let mut components = Vec::new();
+        for start in civilized {
+            if !seen.contains(&start) {
+                let component = self.component(start);
+                seen.extend(component.iter().cloned());
+                components.push(component);
+            }
+        }
+        components
+    }
+
+    pub fn main_component(&self) -> &HashSet<(usize, usize)> {
+        &self.connected
+    }
+
+    fn reconnect(&mut self) {
+        self.connected = self.find_main_component();
+    }
+
+    fn find_main_component(&self) -> HashSet<(usize, usize)> {
+        match self.center {
+            Some((x, y)) if self.is_civilized(x, y) => self.component((x, y)),
+            _ => self
+                .components()
+                .into_iter()
+                .max_by_key(|component| component.len())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn is_connected(&self, x: usize, y: usize) -> bool {
+        self.main_component().contains(&(x, y))
+    }
+
+    fn component(&self, start: (usize, usize)) -> HashSet<(usize, usize)> {
+        let mut component = HashSet::new();
+        let mut queue = VecDeque::new();
+        component.insert(start);
+        queue.push_back(start);
+        while let Some((x, y)) = queue.pop_front() {
+            for neighbour in self.neighbours(x, y) {
+                if self.is_civilized(neighbour.0, neighbour.1) && component.insert(neighbour) {
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        component
+    }
+
+    fn neighbours(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let (w, h, _) = self.dimensions();
+        let mut neighbours = Vec::with_capacity(4);
+        if x > 0 {
+            neighbours.push((x - 1, y));
+        }
+        if y > 0 {
+            neighbours.push((x, y - 1));
+        }
+        if x + 1 < w {
+            neighbours.push((x + 1, y));
+        }
+        if y + 1 < h {
+            neighbours.push((x, y + 1));
+        }
+        neighbours
+    }
+
+    fn relocate_center(&mut self) {
+        let old = self.center.unwrap_or((0, 0));
+        let distance = |(x, y): (usize, usize)| {
+            (x as i32 - old.0 as i32).abs() + (y as i32 - old.1 as i32).abs()
+        };
+        self.center = self
+            .components()
+            .into_iter()
+            .max_by_key(|component| component.len())
+            .and_then(|component| {
+                component
+                    .into_iter()
+                    .min_by_key(|&cell| (distance(cell), cell))
+            });
+        debug!("City center moved from {:?} to {:?}", old, self.center);
+        self.reconnect();
+    }
+
+    pub fn restore_center(&mut self) {
+        match self.center {
+            Some((x, y)) if self.is_civilized(x, y) => self.reconnect(),
+            _ => self.relocate_center(),
+        }
     }
 
     pub fn is_civilizable(&self, x: usize, y: usize) -> bool {
-        let (w, h, d) = self.dimensions();
-        (y > 0 && if let Some((_, civilized)) = self.map.get(&(x, y - 1)) {
-            *civilized
-        } else {
-            false
-        }) || (y < h && if let Some((_, civilized)) = self.map.get(&(x, y + 1)) {
-            *civilized
-        } else {
-            false
-        }) || (x > 0 && if let Some((_, civilized)) = self.map.get(&(x - 1, y)) {
-            *civilized
-        } else {
-            false
-        }) || (x < w && if let Some((_, civilized)) = self.map.get(&(x + 1, y)) {
-            *civilized
-        } else {
-            false
-        })
+        self.neighbours(x, y)
+            .iter()
+            .any(|neighbour| self.connected.contains(neighbour))
     }
 }
 
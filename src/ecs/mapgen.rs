@@ -1,29 +1,139 @@
 use super::*;
 use noise::{NoiseFn, Perlin, Seedable};
-use rand;
-use std::collections::HashMap;
+use rand::{self, Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Seed(pub u32);
+
+impl Default for Seed {
+    fn default() -> Seed {
+        Seed(rand::random())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MapSettings {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub slope: f64,
+    pub relief: f64,
+    pub roughness: f64,
+    pub island: f64,
+}
+
+impl Default for MapSettings {
+    fn default() -> MapSettings {
+        PRESETS[0].1
+    }
+}
+
+impl MapSettings {
+    pub fn dimensions(&self) -> (usize, usize, usize) {
+        (self.width, self.height, self.depth)
+    }
+}
+
+pub const MIN_DIMENSIONS: (usize, usize, usize) = (4, 4, 8);
+pub const MAX_DIMENSIONS: (usize, usize, usize) = (24, 24, 32);
+
+pub const PRESETS: [(&str, MapSettings); 4] = [
+    (
+        "Coast",
+        MapSettings {
+            width: 8,
+            height: 8,
+            depth: 16,
+            slope: 1.0,
+            relief: 0.5,
+            roughness: 1.0,
+            island: 0.0,
+        },
+    ),
+    (
+        "Small Island",
+        MapSettings {
+            width: 8,
+            height: 8,
+            depth: 16,
+            slope: 0.0,
+            relief: 0.3,
+            roughness: 1.5,
+            island: 0.6,
+        },
+    ),
+    (
+        "Long Coast",
+        MapSettings {
+            width: 16,
+            height: 6,
+            depth: 16,
+            slope: 1.2,
+            relief: 0.5,
+            roughness: 2.0,
+            island: 0.0,
+        },
+    ),
+    (
+        "Archipelago",
+        MapSettings {
+            width: 14,
+            height: 14,
+            depth: 16,
+            slope: 0.0,
+            relief: 0.9,
+            roughness: 3.0,
+            island: 0.0,
+        },
+    ),
+];
+
+pub fn generate(world: &mut World) {
+    let mut generator = GenerateMap;
+    <GenerateMap as System>::setup(&mut generator, &mut world.res);
+    generator.run_now(&world.res);
+    world.maintain();
+}
 
 pub struct GenerateMap;
 
 impl<'a> System<'a> for GenerateMap {
     type SystemData = (
         Entities<'a>,
+        Read<'a, Seed>,
+        Read<'a, MapSettings>,
         Write<'a, Grid>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Tile>,
     );
 
-    fn run(&mut self, (entities, mut grid, mut positions, mut tiles): Self::SystemData) {
+    fn run(
+        &mut self,
+        (entities, seed, settings, mut grid, mut positions, mut tiles): Self::SystemData,
+    ) {
         let (w, h, d) = grid.dimensions();
-        let noise = Perlin::new().set_seed(rand::random());
+        let sea = sea_level(d);
+        debug!("Generating {}x{}x{} map from seed {}", w, h, d, seed.0);
+        let noise = Perlin::new().set_seed(seed.0);
         let mut map = HashMap::new();
         for x in 0..w {
             for y in 0..h {
-                let bound = (y as f64
-                    + 0.5 * d as f64
+                let (dx, dy) = (
+                    (x as f64 + 0.5) / w as f64 - 0.5,
+                    (y as f64 + 0.5) / h as f64 - 0.5,
+                );
+                let centrality = (1.0 - 2.0 * (dx * dx + dy * dy).sqrt()).max(0.0);
+                let bound = (settings.slope * y as f64
+                    + settings.relief * d as f64
                         * noise
-                            .get([(1.0 + x as f64 / w as f64), (1.0 + y as f64 / h as f64)])
-                            .abs())
+                            .get([
+                                (1.0 + settings.roughness * x as f64 / w as f64),
+                                (1.0 + settings.roughness * y as f64 / h as f64),
+                            ])
+                            .abs()
+                    + settings.island * d as f64 * centrality)
                     .max(1.0)
                     .min(d as f64 - 1.0);
                 for z in 0..(bound.floor() as usize) {
@@ -38,7 +148,7 @@ impl<'a> System<'a> for GenerateMap {
         }
         for x in 0..w {
             for y in 0..h {
-                for z in 0..4 {
+                for z in 0..sea {
                     if !map.contains_key(&(x, y, z)) {
                         let entity = entities.create();
                         positions
@@ -52,7 +162,7 @@ impl<'a> System<'a> for GenerateMap {
         }
         for x in 0..w {
             for y in 0..h {
-                for z in 4..(d / 2) {
+                for z in sea..(d / 2) {
                     if !map.contains_key(&(x, y, z))
                         && noise.get([
                             (1.0 + 2.0 * x as f64 / w as f64),
@@ -82,24 +192,26 @@ impl<'a> System<'a> for GenerateMap {
             }
         }
         let mut replaced = Vec::new();
+        let mut harbour = false;
         'outer: for y in 1..h {
             for x in (0..w).rev() {
                 if {
-                    if let Some((_, tile)) = map.get(&(x, y, 3)) {
+                    if let Some((_, tile)) = map.get(&(x, y, sea - 1)) {
                         match tile {
-                            Tile::Terrain => match map.get(&(x, y, 4)) {
+                            Tile::Terrain => match map.get(&(x, y, sea)) {
                                 Some((entity, tile)) => match tile {
                                     Tile::Trees => {
-                                        debug!("replacing {} {} {}", x, y, 4);
+                                        debug!("replacing {} {} {}", x, y, sea);
                                         *tiles.get_mut(*entity).unwrap() =
                                             Tile::Structure(Structure::Fishery);
                                         grid.new_position(
                                             Tile::Structure(Structure::Fishery),
                                             x,
                                             y,
-                                            4,
+                                            sea,
                                         );
                                         replaced.push(*entity);
+                                        harbour = true;
                                         break 'outer;
                                         false
                                     }
@@ -117,21 +229,68 @@ impl<'a> System<'a> for GenerateMap {
                     positions
                         .insert(
                             entity,
-                            grid.new_position(Tile::Structure(Structure::Fishery), x, y, 4),
+                            grid.new_position(Tile::Structure(Structure::Fishery), x, y, sea),
                         )
                         .unwrap();
                     tiles
                         .insert(entity, Tile::Structure(Structure::Fishery))
                         .unwrap();
-                    map.insert((x, y, 4), (entity, Tile::Structure(Structure::Fishery)));
+                    map.insert((x, y, sea), (entity, Tile::Structure(Structure::Fishery)));
+                    harbour = true;
                     break 'outer;
                 }
             }
         }
+        if !harbour {
+            let (x, y, top) = {
+                let terrain_top = |x: usize, y: usize| {
+                    (0..d)
+                        .rev()
+                        .find(|z| match map.get(&(x, y, *z)) {
+                            Some((_, Tile::Terrain)) => true,
+                            _ => false,
+                        })
+                        .unwrap_or(0)
+                };
+                (1..h)
+                    .flat_map(|y| (0..w).rev().map(move |x| (x, y)))
+                    .map(|(x, y)| (x, y, terrain_top(x, y)))
+                    .min_by_key(|&(_, _, top)| {
+                        if top + 1 >= sea {
+                            (false, top)
+                        } else {
+                            (true, sea - top)
+                        }
+                    })
+                    .unwrap()
+            };
+            debug!("No shore at sea level, building the harbour at {} {}", x, y);
+            for z in (top + 1)..sea {
+                let (entity, _) = map[&(x, y, z)];
+                *tiles.get_mut(entity).unwrap() = Tile::Terrain;
+                positions
+                    .insert(entity, grid.new_position(Tile::Terrain, x, y, z))
+                    .unwrap();
+                map.insert((x, y, z), (entity, Tile::Terrain));
+            }
+            let z = (top + 1).max(sea);
+            let entity = match map.get(&(x, y, z)) {
+                Some((entity, _)) => *entity,
+                None => entities.create(),
+            };
+            positions
+                .insert(entity, grid.new_position(Tile::Structure(Structure::Fishery), x, y, z))
+                .unwrap();
+            tiles
+                .insert(entity, Tile::Structure(Structure::Fishery))
+                .unwrap();
+            map.insert((x, y, z), (entity, Tile::Structure(Structure::Fishery)));
+            replaced.push(entity);
+        }
         let mut houses = 0;
         'outer: for x in (0..w).rev() {
             for y in 1..h {
-                for z in 4..d / 2 {
+                for z in sea..d / 2 {
                     if {
                         if let Some((_, tile)) = map.get(&(x, y, z - 1)) {
                             match tile {
@@ -191,7 +350,7 @@ impl<'a> System<'a> for GenerateMap {
         }
         'outer: for y in 1..h {
             for x in (0..w).rev() {
-                for z in 4..d / 2 {
+                for z in sea..d / 2 {
                     if {
                         if let Some((_, tile)) = map.get(&(x, y, z - 1)) {
                             match tile {
@@ -207,7 +366,7 @@ impl<'a> System<'a> for GenerateMap {
                                                         Tile::Structure(Structure::Powerplant),
                                                         x,
                                                         y,
-                                                        4,
+                                                        z,
                                                     );
                                                     replaced.push(*entity);
                                                     break 'outer;
@@ -243,156 +402,321 @@ impl<'a> System<'a> for GenerateMap {
                 }
             }
         }
-        grid.current_sealevel = 3;
+        grid.current_sealevel = sea - 1;
     }
 }
 
+fn sea_level(depth: usize) -> usize {
+    (depth / 4).max(2)
+}
+
+const BREACH_ODDS: u32 = 4;
+
+fn flood_rng(seed: u32, sealevel: usize) -> XorShiftRng {
+    let key = seed ^ (sealevel as u32).wrapping_mul(0x9e37_79b9);
+    let mut bytes = [0u8; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (key.rotate_left(i as u32 * 8) & 0xff) as u8 ^ 0xa5;
+    }
+    XorShiftRng::from_seed(bytes)
+}
+
 pub struct Flood;
 
 impl<'a> System<'a> for Flood {
     type SystemData = (
         Entities<'a>,
+        Read<'a, Seed>,
+        Read<'a, StructureDefs>,
         Write<'a, Grid>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Tile>,
     );
 
-    fn run(&mut self, (entities, mut grid, mut positions, mut tiles): Self::SystemData) {
-        let (w, h, d) = grid.dimensions();
-        let sealevel = grid.current_sealevel;
-        let mut map = HashMap::new();
-        {
-            let mut floodable = (&*entities, &mut positions, &mut tiles)
-                .join()
-                .filter(|(_, pos, _)| pos.z() < sealevel + 2)
-                .collect::<Vec<_>>();
-            let first_plane = floodable
-                .iter()
-                .filter(|(_, pos, _)| pos.z() == sealevel + 1)
-                .collect::<Vec<_>>();
-            let first_row = first_plane
-                .iter()
-                .filter(|(_, pos, _)| pos.y() == 0)
-                .collect::<Vec<_>>();
-            for x in 0..w {
-                if let Some((entity, pos, tile)) = first_row.iter().find(|(_, pos, _)| pos.x() == x)
-                {
-                    match tile {
-                        Tile::Terrain => (),
-                        _ => {
-                            map.insert((x, 0, sealevel + 1), Some(*entity));
-                        }
-                    }
-                } else {
-                    map.insert((x, 0, sealevel + 1), None);
+    fn run(
+        &mut self,
+        (entities, seed, defs, mut grid, mut positions, mut tiles): Self::SystemData,
+    ) {
+        let (w, h, _) = grid.dimensions();
+        let level = grid.current_sealevel + 1;
+        let mut ground = HashMap::new();
+        let mut walls = HashMap::new();
+        let mut columns = HashMap::new();
+        for (entity, pos, tile) in (&*entities, &positions, &tiles).join() {
+            match tile {
+                Tile::Terrain => {
+                    let height = ground.entry((pos.x(), pos.y())).or_insert(0);
+                    *height = (*height).max(pos.z() + 1);
                 }
+                Tile::Structure(structure) => if defs.get(*structure).flood_rating > 0 {
+                    walls.insert((pos.x(), pos.y()), (*structure, pos.z()));
+                },
+                _ => (),
             }
-            for y in 1..h {
-                for x in 0..w {
-                    flood_check_part(
-                        x,
-                        y,
-                        sealevel,
-                        (w, h, d),
-                        &mut map,
-                        &floodable,
-                        &first_plane,
-                    );
-                }
-                for x in (0..w).rev() {
-                    flood_check_part(
-                        x,
-                        y,
-                        sealevel,
-                        (w, h, d),
-                        &mut map,
-                        &floodable,
-                        &first_plane,
-                    );
-                }
+            if pos.z() <= level {
+                columns
+                    .entry((pos.x(), pos.y()))
+                    .or_insert_with(Vec::new)
+                    .push((entity, pos.z(), *tile));
             }
-            for y in (1..h).rev() {
-                for x in 0..w {
-                    flood_check_part(
-                        x,
-                        y,
-                        sealevel,
-                        (w, h, d),
-                        &mut map,
-                        &floodable,
-                        &first_plane,
-                    );
-                }
-                for x in (0..w).rev() {
-                    flood_check_part(
-                        x,
-                        y,
-                        sealevel,
-                        (w, h, d),
-                        &mut map,
-                        &floodable,
-                        &first_plane,
-                    );
-                }
+        }
+        let mut barriers = ground.clone();
+        let mut rng = flood_rng(seed.0, grid.current_sealevel);
+        let mut sorted_walls = walls.iter().collect::<Vec<_>>();
+        sorted_walls.sort_by_key(|((x, y), _)| (*y, *x));
+        for ((x, y), (structure, z)) in sorted_walls {
+            let def = defs.get(*structure);
+            let strained = !def.holds_back(*z, level + 1);
+            if def.holds_back(*z, level) && !(strained && rng.gen_range(0, BREACH_ODDS) == 0) {
+                let height = barriers.entry((*x, *y)).or_insert(0);
+                *height = (*height).max(level + 1);
             }
         }
-        for ((x, y, z), entity) in &map {
-            if let Some(entity) = entity {
-                *tiles.get_mut(*entity).unwrap() = Tile::Water;
-                grid.uncivilize(*x, *y);
-            } else {
-                let entity = entities.create();
-                positions
-                    .insert(entity, grid.new_position(Tile::Water, *x, *y, *z))
-                    .unwrap();
-                tiles.insert(entity, Tile::Water).unwrap();
+        let mut new_water = Vec::new();
+        for (x, y) in flood_fill((w, h), level, &barriers) {
+            if let Some((_, z)) = walls.get(&(x, y)) {
+                info!("Sea wall at {} {} {} breached", x, y, z);
+            }
+            let bottom = ground.get(&(x, y)).cloned().unwrap_or(0);
+            let stack = columns.get(&(x, y));
+            for z in bottom..(level + 1) {
+                match stack.and_then(|stack| stack.iter().find(|(_, tile_z, _)| *tile_z == z)) {
+                    Some((_, _, Tile::Terrain)) | Some((_, _, Tile::Water)) => (),
+                    Some((entity, _, _)) => {
+                        *tiles.get_mut(*entity).unwrap() = Tile::Water;
+                        grid.uncivilize(x, y);
+                    }
+                    None => new_water.push((x, y, z)),
+                }
             }
         }
+        for (x, y, z) in new_water {
+            let entity = entities.create();
+            positions
+                .insert(entity, grid.new_position(Tile::Water, x, y, z))
+                .unwrap();
+            tiles.insert(entity, Tile::Water).unwrap();
+        }
         grid.current_sealevel += 1;
     }
 }
 
-fn flood_check_part(
-    x: usize,
-    y: usize,
-    sealevel: usize,
-    (w, h, d): (usize, usize, usize),
-    map: &mut HashMap<(usize, usize, usize), Option<Entity>>,
-    floodable: &Vec<(Entity, &mut Position, &mut Tile)>,
-    first_plane: &Vec<&(Entity, &mut Position, &mut Tile)>,
-) {
-    if !map.contains_key(&(x, y, sealevel + 1))
-        && ((y > 0 && map.contains_key(&(x, y - 1, sealevel + 1)))
-            || (y < h && map.contains_key(&(x, y + 1, sealevel + 1)))
-            || (x > 0 && map.contains_key(&(x - 1, y, sealevel + 1)))
-            || (x < w && map.contains_key(&(x + 1, y, sealevel + 1))))
-    {
-        if let Some((entity, pos, tile)) = first_plane
+pub fn flood_fill(
+    (w, h): (usize, usize),
+    level: usize,
+    ground: &HashMap<(usize, usize), usize>,
+) -> HashSet<(usize, usize)> {
+    let passable = |x: usize, y: usize| ground.get(&(x, y)).cloned().unwrap_or(0) <= level;
+    let mut flooded = HashSet::new();
+    let mut queue = VecDeque::new();
+    for x in 0..w {
+        for y in 0..h {
+            if (x == 0 || y == 0 || x + 1 == w || y + 1 == h)
+                && passable(x, y)
+                && flooded.insert((x, y))
+            {
+                queue.push_back((x, y));
+            }
+        }
+    }
+    while let Some((x, y)) = queue.pop_front() {
+        let mut neighbours = Vec::with_capacity(4);
+        if x > 0 {
+            neighbours.push((x - 1, y));
+        }
+        if y > 0 {
+            neighbours.push((x, y - 1));
+        }
+        if x + 1 < w {
+            neighbours.push((x + 1, y));
+        }
+        if y + 1 < h {
+            neighbours.push((x, y + 1));
+        }
+        for (x, y) in neighbours {
+            if passable(x, y) && flooded.insert((x, y)) {
+                queue.push_back((x, y));
+            }
+        }
+    }
+    flooded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: usize = 5;
+
+    fn heightmap(rows: &[&str]) -> ((usize, usize), HashMap<(usize, usize), usize>) {
+        let mut ground = HashMap::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, height) in row.chars().enumerate() {
+                ground.insert((x, y), height.to_digit(10).unwrap() as usize);
+            }
+        }
+        ((rows[0].len(), rows.len()), ground)
+    }
+
+    fn dry(rows: &[&str]) -> Vec<(usize, usize)> {
+        let (dimensions, ground) = heightmap(rows);
+        let flooded = flood_fill(dimensions, LEVEL, &ground);
+        let mut dry = ground
+            .keys()
+            .filter(|cell| !flooded.contains(cell))
+            .cloned()
+            .collect::<Vec<_>>();
+        dry.sort_by_key(|&(x, y)| (y, x));
+        dry
+    }
+
+    fn walls(rows: &[&str]) -> Vec<(usize, usize)> {
+        let (_, ground) = heightmap(rows);
+        let mut walls = ground
             .iter()
-            .find(|(_, pos, _)| pos.x() == x && pos.y() == y)
-        {
-            match tile {
-                Tile::Terrain => (),
-                _ => {
-                    map.insert((x, y, sealevel + 1), Some(*entity));
-                }
+            .filter(|(_, height)| **height > LEVEL)
+            .map(|(cell, _)| *cell)
+            .collect::<Vec<_>>();
+        walls.sort_by_key(|&(x, y)| (y, x));
+        walls
+    }
+
+    #[test]
+    fn enclosed_basin_below_sea_level_stays_dry() {
+        let rows = [
+            "0000000",
+            "0999990",
+            "0900090",
+            "0901090",
+            "0900090",
+            "0999990",
+            "0000000",
+        ];
+        let mut expected = walls(&rows);
+        for y in 2..5 {
+            for x in 2..5 {
+                expected.push((x, y));
             }
-        } else {
-            let stack = floodable
-                .iter()
-                .filter(|(_, pos, _)| pos.x() == x && pos.y() == y)
-                .collect::<Vec<_>>();
-            for z in 0..(sealevel + 2) {
-                if let Some((entity, pos, tile)) = stack.iter().find(|(_, pos, _)| pos.z() == z) {
-                    match tile {
-                        Tile::Terrain => (),
-                        _ => {
-                            map.insert((x, y, z), Some(*entity));
-                        }
-                    }
+        }
+        expected.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(dry(&rows), expected);
+    }
+
+    #[test]
+    fn winding_channel_floods_from_the_edge() {
+        let rows = [
+            "9999999",
+            "0009999",
+            "9909999",
+            "9900009",
+            "9999909",
+            "9000009",
+            "9099999",
+        ];
+        assert_eq!(dry(&rows), walls(&rows));
+    }
+
+    #[test]
+    fn diagonal_gap_does_not_leak() {
+        let rows = [
+            "00000",
+            "09990",
+            "09090",
+            "09900",
+            "00000",
+        ];
+        let mut expected = walls(&rows);
+        expected.push((2, 2));
+        expected.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(dry(&rows), expected);
+    }
+
+    fn walled_world(sealevel: usize) -> World {
+        let mut grid = Grid::new(5, 5, 8);
+        let mut spawned = Vec::new();
+        for x in 0..5 {
+            for y in 0..5 {
+                spawned.push((grid.new_position(Tile::Terrain, x, y, 0), Tile::Terrain));
+                let top = if x == 2 && y == 2 {
+                    Tile::Structure(Structure::Housing)
+                } else if (1..4).contains(&x) && (1..4).contains(&y) {
+                    Tile::Structure(Structure::SeaWall)
                 } else {
-                    map.insert((x, y, z), None);
-                }
+                    continue;
+                };
+                spawned.push((grid.new_position(top, x, y, 1), top));
+            }
+        }
+        grid.current_sealevel = sealevel;
+        let defs = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/structures.ron"))
+            .replace("flood_rating: 1", "flood_rating: 2")
+            .parse::<StructureDefs>()
+            .unwrap();
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<Tile>();
+        world.add_resource(Seed(1));
+        world.add_resource(defs);
+        world.add_resource(grid);
+        for (pos, tile) in spawned {
+            world.create_entity().with(pos).with(tile).build();
+        }
+        world
+    }
+
+    fn flooded_tile(sealevel: usize, x: usize, y: usize, z: usize) -> Option<Tile> {
+        let mut world = walled_world(sealevel);
+        Flood.run_now(&world.res);
+        world.maintain();
+        let positions = world.read_storage::<Position>();
+        let tiles = world.read_storage::<Tile>();
+        (&positions, &tiles)
+            .join()
+            .find(|(pos, _)| (pos.x(), pos.y(), pos.z()) == (x, y, z))
+            .map(|(_, tile)| *tile)
+    }
+
+    #[test]
+    fn sea_wall_protects_the_tiles_behind_it() {
+        assert_eq!(flooded_tile(0, 0, 0, 1), Some(Tile::Water));
+        assert_eq!(flooded_tile(0, 1, 1, 1), Some(Tile::Structure(Structure::SeaWall)));
+        assert_eq!(flooded_tile(0, 2, 2, 1), Some(Tile::Structure(Structure::Housing)));
+    }
+
+    #[test]
+    fn sea_wall_is_overtopped_one_level_above_its_rating() {
+        assert_eq!(flooded_tile(2, 1, 1, 1), Some(Tile::Water));
+        assert_eq!(flooded_tile(2, 2, 2, 1), Some(Tile::Water));
+        assert_eq!(flooded_tile(2, 2, 2, 3), Some(Tile::Water));
+    }
+
+    fn starts_with_city(settings: MapSettings, seed: u32) -> bool {
+        let (w, h, d) = settings.dimensions();
+        let mut world = World::new();
+        world.add_resource(Seed(seed));
+        world.add_resource(settings);
+        world.add_resource(Grid::new(w, h, d));
+        generate(&mut world);
+        let grid = world.read_resource::<Grid>();
+        match grid.city_center() {
+            Some((x, y)) => grid.is_civilized(x, y),
+            None => false,
+        }
+    }
+
+    #[test]
+    fn every_preset_and_the_smallest_map_start_with_a_city() {
+        let (width, height, depth) = MIN_DIMENSIONS;
+        for (name, settings) in PRESETS.iter() {
+            let smallest = MapSettings {
+                width,
+                height,
+                depth,
+                ..*settings
+            };
+            for seed in 0..8 {
+                assert!(starts_with_city(*settings, seed), "{} seed {}", name, seed);
+                assert!(starts_with_city(smallest, seed), "smallest {} seed {}", name, seed);
             }
         }
     }
@@ -4,12 +4,19 @@ use specs::world::Index;
 use gui;
 use time::Time;
 
+pub mod camera;
+pub mod construction;
 pub mod grid;
 pub mod mapgen;
+pub mod structures;
 pub mod tile;
 
+pub use self::camera::Camera;
 pub use self::grid::Grid;
 pub use self::grid::Position;
+pub use self::mapgen::{MapSettings, Seed};
+pub use self::structures::{StructureDef, StructureDefs};
+pub use self::tile::MapView;
 pub use self::tile::Structure;
 pub use self::tile::Tile;
 pub use self::tile::TILE_SIZE;
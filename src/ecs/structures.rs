@@ -0,0 +1,156 @@
+use super::*;
+use ggez::graphics::Color;
+use ron;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use assets::SpriteHandle;
+
+const EMBEDDED_STRUCTURES: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/structures.ron"));
+
+fn default_tint() -> (f32, f32, f32, f32) {
+    (1.0, 1.0, 1.0, 1.0)
+}
+
+fn default_sprite_offset() -> f32 {
+    -0.5
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StructureDef {
+    pub structure: Structure,
+    pub name: String,
+    #[serde(default)]
+    pub article: String,
+    pub sprite: SpriteHandle,
+    #[serde(default = "default_tint")]
+    pub tint: (f32, f32, f32, f32),
+    #[serde(default = "default_sprite_offset")]
+    pub sprite_offset: f32,
+    #[serde(default)]
+    pub food: i32,
+    #[serde(default)]
+    pub power: i32,
+    #[serde(default)]
+    pub nature: i32,
+    #[serde(default)]
+    pub nature_if_powered: i32,
+    #[serde(default)]
+    pub housing: i32,
+    #[serde(default)]
+    pub requires_shore: bool,
+    // How many levels of water above the ground it stands on a wall keeps out.
+    #[serde(default)]
+    pub flood_rating: usize,
+    pub menu_position: (f32, f32),
+}
+
+impl StructureDef {
+    pub fn color(&self) -> Color {
+        Color::new(self.tint.0, self.tint.1, self.tint.2, self.tint.3)
+    }
+
+    pub fn effects(&self) -> String {
+        let amounts = [
+            (self.housing, "housing"),
+            (self.food, "food"),
+            (self.power, "power"),
+            (self.nature + self.nature_if_powered, "nature"),
+        ];
+        let gains = amounts.iter().filter(|(amount, _)| *amount > 0);
+        let losses = amounts.iter().filter(|(amount, _)| *amount < 0);
+        let mut effects = gains
+            .chain(losses)
+            .map(|(amount, name)| format!("{:+} {}", amount, name))
+            .collect::<Vec<_>>();
+        if self.flood_rating > 0 {
+            effects.push(format!("holds back {} sea level", self.flood_rating));
+        }
+        effects.join(", ")
+    }
+
+    pub fn holds_back(&self, z: usize, level: usize) -> bool {
+        self.flood_rating > 0 && level < z + self.flood_rating
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct StructureFile {
+    structures: Vec<StructureDef>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StructureDefs {
+    order: Vec<Structure>,
+    defs: HashMap<Structure, StructureDef>,
+}
+
+impl Default for StructureDefs {
+    fn default() -> StructureDefs {
+        StructureDefs::load()
+    }
+}
+
+impl StructureDefs {
+    pub fn load() -> StructureDefs {
+        for path in structures_paths() {
+            match fs::read_to_string(&path) {
+                Ok(source) => match source.parse() {
+                    Ok(defs) => {
+                        info!("Loaded structures from {}", path.display());
+                        return defs;
+                    }
+                    Err(e) => warn!("Ignoring {}: {}", path.display(), e),
+                },
+                Err(e) => debug!("Skipping {}: {}", path.display(), e),
+            }
+        }
+        EMBEDDED_STRUCTURES
+            .parse()
+            .expect("Invalid embedded structures.ron")
+    }
+
+    pub fn get(&self, structure: Structure) -> &StructureDef {
+        self.defs
+            .get(&structure)
+            .unwrap_or_else(|| panic!("{:?} is missing from structures.ron", structure))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StructureDef> {
+        self.order.iter().map(move |structure| &self.defs[structure])
+    }
+}
+
+impl FromStr for StructureDefs {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<StructureDefs, String> {
+        let file: StructureFile = ron::de::from_str(source).map_err(|e| e.to_string())?;
+        let mut order = Vec::new();
+        let mut defs = HashMap::new();
+        for def in file.structures {
+            if defs.contains_key(&def.structure) {
+                return Err(format!("{:?} is defined more than once", def.structure));
+            }
+            order.push(def.structure);
+            defs.insert(def.structure, def);
+        }
+        Ok(StructureDefs { order, defs })
+    }
+}
+
+fn structures_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_owned()))
+    {
+        paths.push(dir.join("assets").join("structures.ron"));
+    }
+    paths.push(PathBuf::from("assets").join("structures.ron"));
+    paths
+}
@@ -16,7 +16,7 @@ pub enum Tile {
     Structure(Structure),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Structure {
     Housing,
     Sanctuary,
@@ -24,6 +24,24 @@ pub enum Structure {
     Renewables,
     Farm,
     Fishery,
+    SeaWall,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MapView {
+    pub sealevel: usize,
+    pub dimensions: (usize, usize, usize),
+    pub camera: Camera,
+}
+
+impl MapView {
+    pub fn new(grid: &Grid, camera: &Camera) -> MapView {
+        MapView {
+            sealevel: grid.current_sealevel,
+            dimensions: grid.dimensions(),
+            camera: *camera,
+        }
+    }
 }
 
 impl Tile {
@@ -31,97 +49,53 @@ impl Tile {
         &self,
         ctx: &mut Context,
         assets: &Assets,
+        defs: &StructureDefs,
         pos: &Position,
-        sealevel: usize,
-        depth: usize,
-        is_top: bool,
+        view: &MapView,
     ) -> GameResult {
+        let (sealevel, depth, camera) = (view.sealevel, view.dimensions.2, &view.camera);
+        let screen = map_pos_to_screen(pos, view.dimensions, camera);
+        let sprite_offset = na::Vector2::new(-TILE_SIZE.0, -0.5 * TILE_SIZE.1) * camera.zoom;
         match self {
             Tile::Water => graphics::draw(
                 ctx,
                 assets.fetch_mesh(MeshHandle::Tile),
                 DrawParam::new()
-                    .dest(map_pos_to_screen(pos))
+                    .dest(screen)
                     .color(map_pos_to_water_color(pos.z(), sealevel, depth))
-                    .scale(na::Vector2::new(TILE_SIZE.0, TILE_SIZE.1)),
+                    .scale(na::Vector2::new(TILE_SIZE.0, TILE_SIZE.1) * camera.zoom),
             ),
             Tile::Terrain => graphics::draw(
                 ctx,
                 assets.fetch_sprite(SpriteHandle::TileSprite),
                 DrawParam::new()
-                    .dest(
-                        map_pos_to_screen(pos) + na::Vector2::new(-TILE_SIZE.0, -0.5 * TILE_SIZE.1),
-                    )
-                    .color(map_pos_to_terrain_color(pos.z(), sealevel, depth)),
+                    .dest(screen + sprite_offset)
+                    .color(map_pos_to_terrain_color(pos.z(), sealevel, depth))
+                    .scale(camera.scale()),
             ),
             Tile::Trees => graphics::draw(
                 ctx,
                 assets.fetch_sprite(SpriteHandle::Trees),
                 DrawParam::new()
-                    .dest(
-                        map_pos_to_screen(pos) + na::Vector2::new(-TILE_SIZE.0, -0.5 * TILE_SIZE.1),
-                    )
-                    .color(Color::new(0.3, 0.8, 0.3, 1.0)),
+                    .dest(screen + sprite_offset)
+                    .color(Color::new(0.3, 0.8, 0.3, 1.0))
+                    .scale(camera.scale()),
             ),
-            Tile::Structure(structure) => match structure {
-                Structure::Housing => graphics::draw(
-                    ctx,
-                    assets.fetch_sprite(SpriteHandle::Housing),
-                    DrawParam::new()
-                        .dest(
-                            map_pos_to_screen(pos)
-                                + na::Vector2::new(-TILE_SIZE.0, -0.5 * TILE_SIZE.1),
-                        )
-                        .color(Color::new(0.7, 0.7, 0.9, 1.0)),
-                ),
-                Structure::Sanctuary => graphics::draw(
-                    ctx,
-                    assets.fetch_sprite(SpriteHandle::Sanctuary),
-                    DrawParam::new()
-                        .dest(
-                            map_pos_to_screen(pos)
-                                + na::Vector2::new(-TILE_SIZE.0, -0.5 * TILE_SIZE.1),
-                        )
-                        .color(Color::new(0.9, 0.9, 0.9, 1.0)),
-                ),
-                Structure::Powerplant => graphics::draw(
+            Tile::Structure(structure) => {
+                let def = defs.get(*structure);
+                graphics::draw(
                     ctx,
-                    assets.fetch_sprite(SpriteHandle::Powerplant),
+                    assets.fetch_sprite(def.sprite),
                     DrawParam::new()
                         .dest(
-                            map_pos_to_screen(pos)
-                                + na::Vector2::new(-TILE_SIZE.0, -0.5 * TILE_SIZE.1),
+                            screen
+                                + na::Vector2::new(-TILE_SIZE.0, def.sprite_offset * TILE_SIZE.1)
+                                    * camera.zoom,
                         )
-                        .color(Color::new(0.9, 0.9, 0.9, 1.0)),
-                ),
-                Structure::Renewables => graphics::draw(
-                    ctx,
-                    assets.fetch_sprite(SpriteHandle::Renewables),
-                    DrawParam::new()
-                        .dest(
-                            map_pos_to_screen(pos)
-                                + na::Vector2::new(-TILE_SIZE.0, -0.5 * TILE_SIZE.1),
-                        )
-                        .color(Color::new(0.9, 0.9, 0.9, 1.0)),
-                ),
-                Structure::Farm => graphics::draw(
-                    ctx,
-                    assets.fetch_sprite(SpriteHandle::Farm),
-                    DrawParam::new().dest(
-                        map_pos_to_screen(pos) + na::Vector2::new(-TILE_SIZE.0, -0.4 * TILE_SIZE.1),
-                    ),
-                ),
-                Structure::Fishery => graphics::draw(
-                    ctx,
-                    assets.fetch_sprite(SpriteHandle::Fishery),
-                    DrawParam::new()
-                        .dest(
-                            map_pos_to_screen(pos)
-                                + na::Vector2::new(-TILE_SIZE.0, -0.5 * TILE_SIZE.1),
-                        )
-                        .color(Color::new(0.9, 0.9, 0.9, 1.0)),
-                ),
-            },
+                        .color(def.color())
+                        .scale(camera.scale()),
+                )
+            }
         }
     }
 
@@ -129,52 +103,91 @@ impl Tile {
         &self,
         ctx: &mut Context,
         assets: &Assets,
+        defs: &StructureDefs,
         pos: &Position,
-    ) -> GameResult<bool> {
-        let z = pos.z();
-        let pos = map_pos_to_screen(pos);
-        if hit_test(ctx, pos) {
-            graphics::draw(
-                ctx,
-                assets.fetch_mesh(MeshHandle::TileSelector),
-                DrawParam::new().dest(pos).color(random_color()),
-            )?;
-            //let pos = pos - na::Vector2::new(0.0, TILE_SIZE.1);
-            let text = Text::new(match self {
-                Tile::Water => "Water",
-                Tile::Terrain => "Terrain",
-                Tile::Trees => "Trees",
-                Tile::Structure(s) => match s {
-                    Structure::Housing => "Housing",
-                    Structure::Sanctuary => "Polar Bear Sanctuary",
-                    Structure::Powerplant => "Powerplant",
-                    Structure::Renewables => "Renewables",
-                    Structure::Farm => "Farm",
-                    Structure::Fishery => "Fishing Pier",
-                },
-            });
-            gui::draw_tooltip(ctx, pos, &text);
-            Ok(true)
-        } else {
-            Ok(false)
+        view: &MapView,
+    ) -> GameResult {
+        let camera = &view.camera;
+        let pos = map_pos_to_screen(pos, view.dimensions, camera);
+        graphics::draw(
+            ctx,
+            assets.fetch_mesh(MeshHandle::TileSelector),
+            DrawParam::new()
+                .dest(pos)
+                .color(random_color())
+                .scale(camera.scale()),
+        )?;
+        //let pos = pos - na::Vector2::new(0.0, TILE_SIZE.1);
+        let text = Text::new(match self {
+            Tile::Water => "Water",
+            Tile::Terrain => "Terrain",
+            Tile::Trees => "Trees",
+            Tile::Structure(s) => defs.get(*s).name.as_str(),
+        });
+        gui::draw_tooltip(ctx, pos, &text)
+    }
+}
+
+pub fn pick(point: na::Point2<f32>, grid: &Grid, camera: &Camera) -> Option<(usize, usize, usize)> {
+    let dimensions = grid.dimensions();
+    let (w, h, d) = camera.view_dimensions(dimensions);
+    let offset = camera.to_map(point) - map_origin((w, h, d));
+    for z in (0..d + 2).rev() {
+        let a = offset.x / TILE_SIZE.0;
+        let b = 2.0 * (offset.y + z as f32 * TILE_SIZE.0 * 0.25) / TILE_SIZE.1;
+        let (x, y) = ((0.5 * (a + b)).round(), (0.5 * (a - b)).round());
+        if x < 0.0 || y < 0.0 || x >= w as f32 || y >= h as f32 {
+            continue;
+        }
+        let (x, y) = camera.grid_coords((x as usize, y as usize), dimensions);
+        if grid.height(x, y) == Some(z) {
+            return Some((x, y, z));
         }
     }
+    None
 }
 
-pub fn hit_test(ctx: &Context, pos: na::Point2<f32>) -> bool {
+pub fn pick_mouse(ctx: &Context, grid: &Grid, camera: &Camera) -> Option<(usize, usize, usize)> {
     let mouse = mouse::get_position(ctx);
+    pick(na::Point2::new(mouse.x, mouse.y), grid, camera)
+}
+
+pub fn hit_test(ctx: &Context, camera: &Camera, pos: na::Point2<f32>) -> bool {
+    let mouse = mouse::get_position(ctx);
+    let (w, h) = (TILE_SIZE.0 * camera.zoom, TILE_SIZE.1 * camera.zoom);
     let (x, y) = ((mouse.x - pos.x).abs(), (mouse.y - pos.y).abs());
-    x < TILE_SIZE.0 && y < 0.5 * TILE_SIZE.1 && x / TILE_SIZE.0 + 0.5 * y / TILE_SIZE.1 < 1.0
+    x < w && y < 0.5 * h && x / w + 0.5 * y / h < 1.0
 }
 
-pub fn map_pos_to_screen(pos: &Position) -> na::Point2<f32> {
+pub fn map_origin((w, h, d): (usize, usize, usize)) -> na::Point2<f32> {
     na::Point2::new(
-        100.0 - (320.0 / TILE_SIZE.0 as f32)
-            + (pos.x() as f32 * TILE_SIZE.0)
-            + (pos.y() as f32 * TILE_SIZE.1),
-        240.0 + (pos.x() as f32 * TILE_SIZE.0 * 0.5)
-            - (pos.y() as f32 * TILE_SIZE.1 * 0.5)
-            - (pos.z() as f32 * TILE_SIZE.0 * 0.25),
+        320.0 - (w + h - 2) as f32 * 0.5 * TILE_SIZE.0,
+        200.0 + (h as f32 - w as f32) * 0.25 * TILE_SIZE.1 + d as f32 * 0.0625 * TILE_SIZE.1,
+    )
+}
+
+pub fn map_pos_to_screen(
+    pos: &Position,
+    dimensions: (usize, usize, usize),
+    camera: &Camera,
+) -> na::Point2<f32> {
+    map_coords_to_screen((pos.x(), pos.y(), pos.z()), dimensions, camera)
+}
+
+pub fn map_coords_to_screen(
+    (x, y, z): (usize, usize, usize),
+    dimensions: (usize, usize, usize),
+    camera: &Camera,
+) -> na::Point2<f32> {
+    let (x, y) = camera.view_coords((x, y), dimensions);
+    camera.to_screen(
+        map_origin(camera.view_dimensions(dimensions))
+            + na::Vector2::new(
+                (x as f32 * TILE_SIZE.0) + (y as f32 * TILE_SIZE.1),
+                (x as f32 * TILE_SIZE.0 * 0.5)
+                    - (y as f32 * TILE_SIZE.1 * 0.5)
+                    - (z as f32 * TILE_SIZE.0 * 0.25),
+            ),
     )
 }
 
@@ -216,3 +229,78 @@ fn map_pos_to_terrain_color(z: usize, s: usize, d: usize) -> Color {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_with_heights<F>(dimensions: (usize, usize, usize), height: F) -> Grid
+    where
+        F: Fn(usize, usize) -> usize,
+    {
+        let (w, h, d) = dimensions;
+        let mut grid = Grid::new(w, h, d);
+        for x in 0..w {
+            for y in 0..h {
+                grid.new_position(Tile::Terrain, x, y, height(x, y));
+            }
+        }
+        grid
+    }
+
+    fn project(grid: &Grid, camera: &Camera, target: (usize, usize, usize)) -> na::Point2<f32> {
+        map_coords_to_screen(target, grid.dimensions(), camera)
+    }
+
+    #[test]
+    fn pick_inverts_projection_for_every_rotation() {
+        let dimensions = (5, 4, 8);
+        let height = |x: usize, y: usize| 1 + (x + y) % 2;
+        let grid = grid_with_heights(dimensions, height);
+        for rotation in 0..4 {
+            let camera = Camera {
+                pan: na::Vector2::new(10.0, -7.0),
+                zoom: 1.25,
+                rotation,
+            };
+            for x in 0..dimensions.0 {
+                for y in 0..dimensions.1 {
+                    let target = (x, y, height(x, y));
+                    let point = project(&grid, &camera, target);
+                    assert_eq!(pick(point, &grid, &camera), Some(target));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pick_prefers_tall_column_in_front() {
+        let dimensions = (4, 4, 8);
+        for rotation in 0..4 {
+            let camera = Camera {
+                rotation,
+                ..Camera::default()
+            };
+            let back = camera.grid_coords((1, 2), dimensions);
+            let front = camera.grid_coords((2, 1), dimensions);
+            let grid = grid_with_heights(dimensions, |x, y| {
+                if (x, y) == front {
+                    5
+                } else {
+                    1
+                }
+            });
+            let hidden = project(&grid, &camera, (back.0, back.1, 1));
+            let tall = project(&grid, &camera, (front.0, front.1, 5));
+            assert_eq!(hidden, tall);
+            assert_eq!(pick(hidden, &grid, &camera), Some((front.0, front.1, 5)));
+        }
+    }
+
+    #[test]
+    fn pick_misses_outside_the_map() {
+        let grid = grid_with_heights((4, 4, 8), |_, _| 1);
+        let camera = Camera::default();
+        assert_eq!(pick(na::Point2::new(-500.0, -500.0), &grid, &camera), None);
+    }
+}
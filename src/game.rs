@@ -5,13 +5,13 @@ use ggez::{Context, GameResult};
 use specs::prelude::*;
 
 use assets::Assets;
+use ecs::StructureDefs;
 use input::{Command, InputExtra, InputHandler};
 use state::{MainMenu, State, Transition};
 use time::Time;
 
 pub struct Game {
     assets: Assets,
-    input: InputHandler,
     state_stack: Vec<Box<State>>,
     world: World,
 }
@@ -21,8 +21,10 @@ impl Game {
         let mut assets = Assets::new(ctx)?;
         let mut world = World::new();
         world.res.entry::<Time>().or_insert_with(Time::new);
+        world.add_resource(StructureDefs::load());
+        world.add_resource(InputHandler::load());
         let mut state_stack = Vec::<Box<State>>::new();
-        state_stack.push(Box::new(MainMenu));
+        state_stack.push(Box::new(MainMenu::new()));
         if let Some(current_state) = state_stack.last_mut() {
             trace!("Starting state {}", &current_state);
             if let Err(e) = current_state.start(ctx, &mut assets, &mut world) {
@@ -31,7 +33,6 @@ impl Game {
         }
         Ok(Game {
             assets,
-            input: InputHandler::default(),
             state_stack,
             world,
         })
@@ -80,6 +81,23 @@ impl Game {
                     }
                 }
             }
+            Transition::PopToRoot => {
+                trace!("Popping states down to the root");
+                while self.state_stack.len() > 1 {
+                    if let Some(mut state) = self.state_stack.pop() {
+                        trace!(" stopping {}", &state);
+                        if let Err(e) = state.stop(ctx, &mut self.assets, &mut self.world) {
+                            error!("Error stopping state {}: {:?}", &state, e);
+                        }
+                    }
+                }
+                if let Some(current_state) = self.state_stack.last_mut() {
+                    trace!("Resuming state {}", &current_state);
+                    if let Err(e) = current_state.resume(ctx, &mut self.assets, &mut self.world) {
+                        error!("Error resuming state {}: {:?}", &current_state, e);
+                    }
+                }
+            }
             Transition::Replace(new_state) => {
                 if let Some(mut old_state) = self.state_stack.pop() {
                     trace!("Replacing state {} with {}", &old_state, &new_state);
@@ -175,13 +193,55 @@ impl EventHandler for Game {
         Ok(())
     }
 
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) {
+        self.world
+            .write_resource::<InputHandler>()
+            .mouse_button_down_event();
+    }
+
     fn mouse_button_up_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
-        let resolved = self.input.mouse_button_up_event(ctx, button, x, y);
+        let resolved = self
+            .world
+            .write_resource::<InputHandler>()
+            .mouse_button_up_event(ctx, button, x, y);
+        self.propagate_input(ctx, resolved);
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, _x: f32, _y: f32, dx: f32, dy: f32) {
+        let resolved = self
+            .world
+            .write_resource::<InputHandler>()
+            .mouse_motion_event(ctx, dx, dy);
+        self.propagate_input(ctx, resolved);
+    }
+
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) {
+        let resolved = self
+            .world
+            .write_resource::<InputHandler>()
+            .mouse_wheel_event(ctx, y);
         self.propagate_input(ctx, resolved);
     }
 
     fn key_down_event(&mut self, ctx: &mut Context, key: KeyCode, mods: KeyMods, repeat: bool) {
-        let resolved = self.input.key_down_event(ctx, key, mods.into(), repeat);
+        let resolved = self
+            .world
+            .write_resource::<InputHandler>()
+            .key_down_event(ctx, key, mods.into(), repeat);
+        self.propagate_input(ctx, resolved);
+    }
+
+    fn text_input_event(&mut self, ctx: &mut Context, character: char) {
+        let resolved = self
+            .world
+            .write_resource::<InputHandler>()
+            .text_input_event(ctx, character);
         self.propagate_input(ctx, resolved);
     }
 
@@ -1,5 +1,8 @@
 use ecs::TILE_SIZE;
-use ggez::graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect, Text, TextFragment, WHITE};
+use ggez::graphics::{
+    self, Color, DrawMode, DrawParam, Mesh, Rect, Scale, Text, TextFragment, WHITE,
+};
+use ggez::input::mouse;
 use ggez::{Context, GameResult};
 use nalgebra as na;
 
@@ -21,6 +24,24 @@ pub fn draw_tooltip(ctx: &mut Context, pos: na::Point2<f32>, text: &Text) -> Gam
     graphics::draw(ctx, text, DrawParam::new().dest(pos - vec))
 }
 
+pub fn is_hovered(ctx: &mut Context, text: &Text, pos: na::Point2<f32>) -> bool {
+    let mouse = mouse::get_position(ctx);
+    let dim = text.dimensions(ctx);
+    mouse.x >= pos.x
+        && mouse.y >= pos.y
+        && mouse.x <= pos.x + dim.0 as f32
+        && mouse.y <= pos.y + dim.1 as f32
+}
+
+pub fn draw_button(ctx: &mut Context, text: &Text, pos: na::Point2<f32>) -> GameResult {
+    let color = if is_hovered(ctx, text, pos) {
+        Color::new(0.5, 1.0, 0.5, 1.0)
+    } else {
+        Color::new(0.0, 0.8, 0.8, 1.0)
+    };
+    graphics::draw(ctx, text, DrawParam::new().dest(pos).color(color))
+}
+
 pub const RED: Color = Color {
     r: 1.0,
     g: 0.2,
@@ -28,7 +49,7 @@ pub const RED: Color = Color {
     a: 1.0,
 };
 
-pub fn draw_score(ctx: &mut Context, time: &super::time::Time) -> GameResult {
+pub fn draw_score(ctx: &mut Context, time: &super::time::Time, seed: u32) -> GameResult {
     let pos = na::Point2::new(0.0, 0.0);
     let mut text = Text::new(TextFragment::new(format!(
         "Turn: {}  Score: {}\n",
@@ -70,6 +91,63 @@ pub fn draw_score(ctx: &mut Context, time: &super::time::Time) -> GameResult {
                 RED
             }),
         )
-        .add(TextFragment::new(format!("Nature: {}\n", time.nature)));
+        .add(TextFragment::new(format!("Nature: {}\n", time.nature)))
+        .add(if time.disconnected > 0 {
+            TextFragment::new(format!("{} structures cut off!\n", time.disconnected)).color(RED)
+        } else {
+            TextFragment::new("")
+        })
+        .add(TextFragment::new(format!("Seed: {}\n", seed)).scale(Scale::uniform(15.0)));
     graphics::draw(ctx, &text, DrawParam::new().dest(pos))
 }
+
+pub fn draw_graph(
+    ctx: &mut Context,
+    area: Rect,
+    label: &str,
+    values: &[i32],
+    color: Color,
+) -> GameResult {
+    let background = Mesh::new_rectangle(ctx, DrawMode::Fill, area)?;
+    graphics::draw(
+        ctx,
+        &background,
+        DrawParam::new().color(Color::new(0.1, 0.1, 0.15, 0.9)),
+    )?;
+    let min = values.iter().cloned().min().unwrap_or(0).min(0);
+    let max = values.iter().cloned().max().unwrap_or(0).max(min + 1);
+    let text = Text::new(
+        TextFragment::new(format!("{} ({} to {})", label, min, max)).scale(Scale::uniform(13.0)),
+    );
+    graphics::draw(
+        ctx,
+        &text,
+        DrawParam::new().dest(na::Point2::new(area.x + 3.0, area.y + 2.0)),
+    )?;
+    let plot = Rect::new(area.x + 4.0, area.y + 18.0, area.w - 8.0, area.h - 22.0);
+    let steps = values.len().max(2) - 1;
+    let point = |i: usize, value: i32| {
+        na::Point2::new(
+            plot.x + plot.w * i as f32 / steps as f32,
+            plot.y + plot.h * (max - value) as f32 / (max - min) as f32,
+        )
+    };
+    if min < 0 {
+        let zero = Mesh::new_polyline(ctx, DrawMode::Line(1.0), &[point(0, 0), point(steps, 0)])?;
+        graphics::draw(
+            ctx,
+            &zero,
+            DrawParam::new().color(Color::new(0.4, 0.4, 0.4, 1.0)),
+        )?;
+    }
+    if values.len() >= 2 {
+        let points = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| point(i, *value))
+            .collect::<Vec<_>>();
+        let line = Mesh::new_polyline(ctx, DrawMode::Line(1.0), &points)?;
+        graphics::draw(ctx, &line, DrawParam::new().color(color))?;
+    }
+    Ok(())
+}
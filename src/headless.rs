@@ -0,0 +1,191 @@
+use specs::prelude::*;
+use std::path::{Path, PathBuf};
+
+use action::{self, GameAction};
+use ecs::*;
+use history::History;
+use replay::{self, ActionLog, Replay};
+use saveload::SaveResult;
+use time::{end_turn, Time};
+
+pub struct Simulation {
+    world: World,
+}
+
+impl Simulation {
+    pub fn new(settings: MapSettings, seed: u32) -> Simulation {
+        let (width, height, depth) = settings.dimensions();
+        let mut world = World::new();
+        world.add_resource(StructureDefs::load());
+        world.add_resource(Seed(seed));
+        world.add_resource(settings);
+        world.add_resource(Grid::new(width, height, depth));
+        world.add_resource(Time::new());
+        world.add_resource(History::default());
+        replay::start_recording(&mut world, seed, settings);
+        mapgen::generate(&mut world);
+        Simulation { world }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn history(&self) -> History {
+        self.world.read_resource::<History>().clone()
+    }
+
+    pub fn export_history(&self, path: &Path) -> SaveResult<(PathBuf, PathBuf)> {
+        self.world.read_resource::<History>().export(path)
+    }
+
+    pub fn replay(&self) -> Option<Replay> {
+        self.world.read_resource::<ActionLog>().0.clone()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.world.read_resource::<Time>().game_over
+    }
+
+    pub fn step(&mut self, action: &GameAction) -> bool {
+        let applied = advance(&mut self.world, action);
+        if applied {
+            replay::record(&self.world, *action);
+        }
+        self.world.maintain();
+        applied
+    }
+
+    pub fn run<'a, I>(&mut self, actions: I) -> i32
+    where
+        I: IntoIterator<Item = &'a GameAction>,
+    {
+        for action in actions {
+            if self.is_over() {
+                break;
+            }
+            self.step(action);
+        }
+        self.world.read_resource::<Time>().score
+    }
+}
+
+pub fn advance(world: &mut World, action: &GameAction) -> bool {
+    let applied = action::perform(world, action);
+    if world.read_resource::<Time>().turn_passed {
+        end_turn(world);
+    }
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bot;
+    use undo::UndoStack;
+
+    const TURN_CAP: usize = 40;
+
+    #[derive(Debug, PartialEq)]
+    struct WorldState {
+        dimensions: (usize, usize, usize),
+        sealevel: usize,
+        cells: Vec<(Option<usize>, bool)>,
+        tiles: Vec<((usize, usize, usize), String)>,
+    }
+
+    fn world_state(simulation: &Simulation) -> WorldState {
+        let world = simulation.world();
+        let grid = world.read_resource::<Grid>();
+        let (w, h, _) = grid.dimensions();
+        let cells = (0..h)
+            .flat_map(|y| (0..w).map(move |x| (x, y)))
+            .map(|(x, y)| (grid.height(x, y), grid.is_civilized(x, y)))
+            .collect();
+        let positions = world.read_storage::<Position>();
+        let tiles = world.read_storage::<Tile>();
+        let mut tiles = (&positions, &tiles)
+            .join()
+            .map(|(pos, tile)| ((pos.x(), pos.y(), pos.z()), format!("{:?}", tile)))
+            .collect::<Vec<_>>();
+        tiles.sort();
+        WorldState {
+            dimensions: grid.dimensions(),
+            sealevel: grid.current_sealevel,
+            cells,
+            tiles,
+        }
+    }
+
+    fn script(simulation: &Simulation) -> Vec<GameAction> {
+        let found = bot::candidates(simulation.world());
+        assert!(found.len() >= 4, "only {} buildable tiles", found.len());
+        vec![
+            GameAction::PickUp(found[0].0, found[0].1),
+            GameAction::Place(found[1].0, found[1].1),
+            GameAction::Build(found[2].0, found[2].1, Structure::Farm),
+            GameAction::Build(found[3].0, found[3].1, Structure::Housing),
+        ]
+    }
+
+    fn play(seed: u32) -> (i32, i32, bool, usize, WorldState) {
+        let mut simulation = Simulation::new(MapSettings::default(), seed);
+        for action in script(&simulation) {
+            assert!(simulation.step(&action), "{:?} was rejected", action);
+        }
+        let score = simulation.run(&[GameAction::SkipTurn; TURN_CAP]);
+        let time = simulation.world().read_resource::<Time>();
+        (
+            score,
+            time.turn,
+            time.game_over,
+            simulation.history().turns.len(),
+            world_state(&simulation),
+        )
+    }
+
+    #[test]
+    fn scripted_actions_replay_identically() {
+        let first = play(1234);
+        let (_, turn, game_over, recorded, _) = first;
+        assert!(turn > 3);
+        assert!(game_over || turn == 3 + TURN_CAP as i32);
+        assert_eq!(recorded, turn as usize);
+        assert_eq!(play(1234), first);
+    }
+
+    #[test]
+    fn same_seed_generates_identical_worlds() {
+        let settings = MapSettings::default();
+        let first = Simulation::new(settings, u32::max_value());
+        let second = Simulation::new(settings, u32::max_value());
+        assert_eq!(world_state(&first), world_state(&second));
+        let other = Simulation::new(settings, 7);
+        assert_ne!(world_state(&first).tiles, world_state(&other).tiles);
+    }
+
+    #[test]
+    fn advance_skips_undo_and_recording() {
+        let mut simulation = Simulation::new(MapSettings::default(), 1234);
+        assert!(advance(simulation.world_mut(), &GameAction::SkipTurn));
+        assert!(simulation.world().res.try_fetch::<UndoStack>().is_none());
+        assert!(simulation.replay().unwrap().actions.is_empty());
+        assert!(simulation.step(&GameAction::SkipTurn));
+        assert_eq!(simulation.replay().unwrap().actions, vec![GameAction::SkipTurn]);
+        assert_eq!(simulation.world().read_resource::<Time>().turn, 2);
+    }
+
+    #[test]
+    fn step_rejects_invalid_actions_without_passing_a_turn() {
+        let mut simulation = Simulation::new(MapSettings::default(), 1234);
+        let (width, height, _) = simulation.world().read_resource::<Grid>().dimensions();
+        assert!(!simulation.step(&GameAction::Build(width, height, Structure::Housing)));
+        assert_eq!(simulation.world().read_resource::<Time>().turn, 0);
+        assert!(simulation.step(&GameAction::SkipTurn));
+        assert_eq!(simulation.world().read_resource::<Time>().turn, 1);
+    }
+}
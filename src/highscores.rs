@@ -0,0 +1,92 @@
+use chrono::{Local, TimeZone};
+use ron;
+use std::fs;
+use std::path::PathBuf;
+
+use saveload::{data_dir, SaveError, SaveResult};
+use time::Time;
+
+const HIGHSCORES_FILE: &str = "highscores.ron";
+pub const MAX_ENTRIES: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: i32,
+    pub turn: i32,
+    pub peak_population: i32,
+    pub seed: u32,
+    pub timestamp: i64,
+}
+
+impl HighScore {
+    pub fn new(time: &Time, seed: u32) -> HighScore {
+        HighScore {
+            score: time.score,
+            turn: time.turn,
+            peak_population: time.peak_population,
+            seed,
+            timestamp: Local::now().timestamp(),
+        }
+    }
+
+    pub fn date(&self) -> String {
+        Local
+            .timestamp(self.timestamp, 0)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScoreTable {
+    fn path() -> PathBuf {
+        data_dir().join(HIGHSCORES_FILE)
+    }
+
+    pub fn load() -> HighScoreTable {
+        match fs::read_to_string(HighScoreTable::path()) {
+            Ok(source) => ron::de::from_str(&source).unwrap_or_else(|e| {
+                error!("Could not parse {}: {}", HIGHSCORES_FILE, e);
+                HighScoreTable::default()
+            }),
+            Err(_) => HighScoreTable::default(),
+        }
+    }
+
+    pub fn save(&self) -> SaveResult {
+        let source = ron::ser::to_string_pretty(self, Default::default())
+            .map_err(|e| SaveError::Serialize(e.to_string()))?;
+        fs::create_dir_all(data_dir())?;
+        fs::write(HighScoreTable::path(), source.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or_else(|| self.entries.len());
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+}
+
+pub fn record(entry: HighScore) -> Option<usize> {
+    let mut table = HighScoreTable::load();
+    let rank = table.insert(entry);
+    if rank.is_some() {
+        if let Err(e) = table.save() {
+            error!("Could not save high scores: {}", e);
+        }
+    }
+    rank
+}
@@ -0,0 +1,100 @@
+use chrono::Local;
+use serde_json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use saveload::{data_dir, SaveError, SaveResult};
+use time::Time;
+
+const CSV_HEADER: &str = "turn,score,population,homeless,food,power,nature,sealevel,\
+                          flood_timer,structures";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct TurnRecord {
+    pub turn: i32,
+    pub score: i32,
+    pub population: i32,
+    pub homeless: i32,
+    pub food: i32,
+    pub power: i32,
+    pub nature: i32,
+    pub sealevel: usize,
+    pub flood_timer: i32,
+    pub structures: usize,
+}
+
+impl TurnRecord {
+    pub fn new(time: &Time, sealevel: usize, structures: usize) -> TurnRecord {
+        TurnRecord {
+            turn: time.turn,
+            score: time.score,
+            population: time.population,
+            homeless: time.homeless,
+            food: time.food,
+            power: time.power,
+            nature: time.nature,
+            sealevel,
+            flood_timer: time.flood_timer,
+            structures,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct History {
+    pub turns: Vec<TurnRecord>,
+    pub structures_built: usize,
+    pub tiles_flooded: usize,
+}
+
+impl History {
+    pub fn series<F>(&self, value: F) -> Vec<i32>
+    where
+        F: Fn(&TurnRecord) -> i32,
+    {
+        self.turns.iter().map(value).collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+        for record in &self.turns {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                record.turn,
+                record.score,
+                record.population,
+                record.homeless,
+                record.food,
+                record.power,
+                record.nature,
+                record.sealevel,
+                record.flood_timer,
+                record.structures
+            ));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> SaveResult<String> {
+        serde_json::to_string_pretty(self).map_err(|e| SaveError::Serialize(e.to_string()))
+    }
+
+    pub fn export(&self, path: &Path) -> SaveResult<(PathBuf, PathBuf)> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let csv = path.with_extension("csv");
+        let json = path.with_extension("json");
+        fs::write(&csv, self.to_csv().as_bytes())?;
+        fs::write(&json, self.to_json()?.as_bytes())?;
+        info!("Exported history to {} and {}", csv.display(), json.display());
+        Ok((csv, json))
+    }
+
+    pub fn export_timestamped(&self) -> SaveResult<(PathBuf, PathBuf)> {
+        let name = format!("history-{}", Local::now().format("%Y%m%d-%H%M%S"));
+        self.export(&data_dir().join("exports").join(name))
+    }
+}
@@ -1,12 +1,22 @@
 use ggez::event::{KeyCode, KeyMods, MouseButton};
-use ggez::input::keyboard;
+use ggez::input::{keyboard, mouse};
 use ggez::Context;
+use ron;
 use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use saveload::{data_dir, SaveError, SaveResult};
+
+const BINDINGS_FILE: &str = "keybindings.ron";
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum Input {
     Key(KeyCode),
     Mouse(MouseButton),
+    MouseDrag(MouseButton),
+    WheelUp,
+    WheelDown,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
@@ -14,17 +24,56 @@ pub enum InputExtra {
     None,
     RepeatedKey(bool),
     XY(i32, i32),
+    Char(char),
+    Input(Input, KeyMods),
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum Command {
     Click,
     ContextMenu,
+    Confirm,
+    Erase,
+    Text,
+    Captured,
+    QuickSave,
+    QuickLoad,
+    SaveMenu,
+    LoadMenu,
+    Undo,
+    Redo,
+    Pan,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    RotateLeft,
+    RotateRight,
+    CursorLeft,
+    CursorRight,
+    CursorUp,
+    CursorDown,
+    Hotkey(usize),
+    Terraform,
+    Pause,
+    ExportHistory,
     Quit,
 }
 
+const DRAG_THRESHOLD: f32 = 4.0;
+const ESSENTIAL_COMMANDS: [Command; 3] = [Command::Click, Command::Pause, Command::Confirm];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BindingsFile {
+    bindings: Vec<(Command, String)>,
+}
+
 pub struct InputHandler {
     bindings: HashMap<Input, Vec<(KeyMods, Command)>>,
+    dragged: f32,
+    capturing: bool,
 }
 
 impl Default for InputHandler {
@@ -45,7 +94,51 @@ impl Default for InputHandler {
                 Input::Mouse(MouseButton::Left),
                 KeyMods::ALT,
                 Command::ContextMenu,
-            );
+            )
+            .bind(Input::Key(KeyCode::Return), KeyMods::NONE, Command::Confirm)
+            .bind(Input::Key(KeyCode::Back), KeyMods::NONE, Command::Erase)
+            .bind(Input::Key(KeyCode::F5), KeyMods::NONE, Command::QuickSave)
+            .bind(Input::Key(KeyCode::F9), KeyMods::NONE, Command::QuickLoad)
+            .bind(Input::Key(KeyCode::S), KeyMods::CTRL, Command::SaveMenu)
+            .bind(Input::Key(KeyCode::L), KeyMods::CTRL, Command::LoadMenu)
+            .bind(Input::Key(KeyCode::Z), KeyMods::CTRL, Command::Undo)
+            .bind(Input::Key(KeyCode::Y), KeyMods::CTRL, Command::Redo)
+            .bind(
+                Input::MouseDrag(MouseButton::Left),
+                KeyMods::NONE,
+                Command::Pan,
+            )
+            .bind(Input::Key(KeyCode::Left), KeyMods::SHIFT, Command::PanLeft)
+            .bind(Input::Key(KeyCode::Right), KeyMods::SHIFT, Command::PanRight)
+            .bind(Input::Key(KeyCode::Up), KeyMods::SHIFT, Command::PanUp)
+            .bind(Input::Key(KeyCode::Down), KeyMods::SHIFT, Command::PanDown)
+            .bind(Input::WheelUp, KeyMods::NONE, Command::ZoomIn)
+            .bind(Input::WheelDown, KeyMods::NONE, Command::ZoomOut)
+            .bind(Input::Key(KeyCode::Equals), KeyMods::NONE, Command::ZoomIn)
+            .bind(Input::Key(KeyCode::Minus), KeyMods::NONE, Command::ZoomOut)
+            .bind(Input::Key(KeyCode::Q), KeyMods::NONE, Command::RotateLeft)
+            .bind(Input::Key(KeyCode::E), KeyMods::NONE, Command::RotateRight)
+            .bind(Input::Key(KeyCode::Left), KeyMods::NONE, Command::CursorLeft)
+            .bind(Input::Key(KeyCode::Right), KeyMods::NONE, Command::CursorRight)
+            .bind(Input::Key(KeyCode::Up), KeyMods::NONE, Command::CursorUp)
+            .bind(Input::Key(KeyCode::Down), KeyMods::NONE, Command::CursorDown)
+            .bind(Input::Key(KeyCode::A), KeyMods::NONE, Command::CursorLeft)
+            .bind(Input::Key(KeyCode::D), KeyMods::NONE, Command::CursorRight)
+            .bind(Input::Key(KeyCode::W), KeyMods::NONE, Command::CursorUp)
+            .bind(Input::Key(KeyCode::S), KeyMods::NONE, Command::CursorDown)
+            .bind(Input::Key(KeyCode::Key1), KeyMods::NONE, Command::Hotkey(0))
+            .bind(Input::Key(KeyCode::Key2), KeyMods::NONE, Command::Hotkey(1))
+            .bind(Input::Key(KeyCode::Key3), KeyMods::NONE, Command::Hotkey(2))
+            .bind(Input::Key(KeyCode::Key4), KeyMods::NONE, Command::Hotkey(3))
+            .bind(Input::Key(KeyCode::Key5), KeyMods::NONE, Command::Hotkey(4))
+            .bind(Input::Key(KeyCode::Key6), KeyMods::NONE, Command::Hotkey(5))
+            .bind(Input::Key(KeyCode::Key7), KeyMods::NONE, Command::Hotkey(6))
+            .bind(Input::Key(KeyCode::Key8), KeyMods::NONE, Command::Hotkey(7))
+            .bind(Input::Key(KeyCode::Key9), KeyMods::NONE, Command::Hotkey(8))
+            .bind(Input::Key(KeyCode::T), KeyMods::NONE, Command::Terraform)
+            .bind(Input::Key(KeyCode::Escape), KeyMods::NONE, Command::Pause)
+            .bind(Input::Key(KeyCode::F12), KeyMods::NONE, Command::ExportHistory)
+            .bind(Input::Key(KeyCode::Q), KeyMods::CTRL, Command::Quit);
         handler
     }
 }
@@ -54,7 +147,133 @@ impl InputHandler {
     pub fn new() -> InputHandler {
         InputHandler {
             bindings: HashMap::new(),
+            dragged: 0.0,
+            capturing: false,
+        }
+    }
+
+    fn bindings_path() -> PathBuf {
+        data_dir().join(BINDINGS_FILE)
+    }
+
+    pub fn load() -> InputHandler {
+        let source = match fs::read_to_string(InputHandler::bindings_path()) {
+            Ok(source) => source,
+            Err(_) => return InputHandler::default(),
+        };
+        let file = match ron::de::from_str::<BindingsFile>(&source) {
+            Ok(file) => file,
+            Err(e) => {
+                error!("Could not parse {}, using defaults: {}", BINDINGS_FILE, e);
+                return InputHandler::default();
+            }
+        };
+        let mut handler = InputHandler::default();
+        let mut overridden = Vec::new();
+        for (command, _) in &file.bindings {
+            if !overridden.contains(command) {
+                handler.unbind_command(*command);
+                overridden.push(*command);
+            }
+        }
+        for (command, name) in file.bindings {
+            match parse_binding(&name) {
+                Some((input, keymods)) => match handler.bound(input, keymods) {
+                    Some(other) if other != command && overridden.contains(&other) => warn!(
+                        "{} is bound to both {:?} and {:?}, keeping {:?}",
+                        name, other, command, other
+                    ),
+                    _ => {
+                        handler.bind(input, keymods, command);
+                    }
+                },
+                None => warn!("Unknown input {:?} bound to {:?}", name, command),
+            }
+        }
+        handler
+    }
+
+    pub fn save(&self) -> SaveResult {
+        let mut file = BindingsFile::default();
+        for (input, bound_action_bunch) in &self.bindings {
+            for (keymods, command) in bound_action_bunch {
+                file.bindings.push((*command, binding_name(*input, *keymods)));
+            }
+        }
+        file.bindings.sort();
+        let source = ron::ser::to_string_pretty(&file, Default::default())
+            .map_err(|e| SaveError::Serialize(e.to_string()))?;
+        fs::create_dir_all(data_dir())?;
+        fs::write(InputHandler::bindings_path(), source.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn bound(&self, input: Input, keymods: KeyMods) -> Option<Command> {
+        self.bindings.get(&input).and_then(|bound_action_bunch| {
+            bound_action_bunch
+                .iter()
+                .find(|bound_action| bound_action.0 == keymods)
+                .map(|bound_action| bound_action.1)
+        })
+    }
+
+    pub fn bindings_for(&self, command: Command) -> Vec<(Input, KeyMods)> {
+        let mut found = self
+            .bindings
+            .iter()
+            .flat_map(|(input, bound_action_bunch)| {
+                bound_action_bunch
+                    .iter()
+                    .filter(|bound_action| bound_action.1 == command)
+                    .map(move |bound_action| (*input, bound_action.0))
+            })
+            .collect::<Vec<_>>();
+        found.sort_by_key(|(input, keymods)| binding_name(*input, *keymods));
+        found
+    }
+
+    pub fn unbind_command(&mut self, command: Command) {
+        for bound_action_bunch in self.bindings.values_mut() {
+            bound_action_bunch.retain(|bound_action| bound_action.1 != command);
+        }
+        self.bindings
+            .retain(|_, bound_action_bunch| !bound_action_bunch.is_empty());
+    }
+
+    pub fn rebind(
+        &mut self,
+        input: Input,
+        keymods: KeyMods,
+        command: Command,
+    ) -> Result<Option<Command>, Command> {
+        let displaced = self.bound(input, keymods).filter(|other| *other != command);
+        if let Some(other) = displaced {
+            if ESSENTIAL_COMMANDS.contains(&other) && self.bindings_for(other).len() == 1 {
+                return Err(other);
+            }
+        }
+        self.unbind_command(command);
+        self.bind(input, keymods, command);
+        Ok(displaced)
+    }
+
+    pub fn capture(&mut self) {
+        self.capturing = true;
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    fn captured(&mut self, input: Input, keymods: KeyMods) -> Option<(Command, InputExtra)> {
+        let name = binding_name(input, keymods);
+        if parse_binding(&name).is_none() {
+            trace!("Ignoring unbindable input {}", name);
+            return None;
         }
+        self.capturing = false;
+        trace!("Captured: {}", name);
+        Some((Command::Captured, InputExtra::Input(input, keymods)))
     }
 
     pub fn bind(&mut self, input: Input, keymods: KeyMods, action: Command) -> &mut InputHandler {
@@ -109,6 +328,10 @@ impl InputHandler {
         None
     }
 
+    pub fn mouse_button_down_event(&mut self) {
+        self.dragged = 0.0;
+    }
+
     pub fn mouse_button_up_event(
         &mut self,
         ctx: &mut Context,
@@ -116,7 +339,14 @@ impl InputHandler {
         x: f32,
         y: f32,
     ) -> Option<(Command, InputExtra)> {
+        if self.dragged > DRAG_THRESHOLD {
+            self.dragged = 0.0;
+            return None;
+        }
         let mods = keyboard::get_active_mods(ctx);
+        if self.capturing {
+            return self.captured(Input::Mouse(button), mods);
+        }
         self.resolve(
             ctx,
             Input::Mouse(button),
@@ -125,6 +355,55 @@ impl InputHandler {
         )
     }
 
+    pub fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        dx: f32,
+        dy: f32,
+    ) -> Option<(Command, InputExtra)> {
+        let button = [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
+            .iter()
+            .cloned()
+            .find(|button| mouse::button_pressed(ctx, *button));
+        if self.capturing {
+            return None;
+        }
+        if let Some(button) = button {
+            self.dragged += dx.abs() + dy.abs();
+            if self.dragged > DRAG_THRESHOLD {
+                let mods = keyboard::get_active_mods(ctx);
+                return self.resolve(
+                    ctx,
+                    Input::MouseDrag(button),
+                    mods,
+                    InputExtra::XY(dx as i32, dy as i32),
+                );
+            }
+        }
+        None
+    }
+
+    pub fn mouse_wheel_event(&mut self, ctx: &mut Context, y: f32) -> Option<(Command, InputExtra)> {
+        let input = if y > 0.0 {
+            Input::WheelUp
+        } else if y < 0.0 {
+            Input::WheelDown
+        } else {
+            return None;
+        };
+        let mods = keyboard::get_active_mods(ctx);
+        if self.capturing {
+            return self.captured(input, mods);
+        }
+        let position = mouse::get_position(ctx);
+        self.resolve(
+            ctx,
+            input,
+            mods,
+            InputExtra::XY(position.x as i32, position.y as i32),
+        )
+    }
+
     pub fn key_down_event(
         &mut self,
         ctx: &mut Context,
@@ -132,6 +411,211 @@ impl InputHandler {
         mods: KeyMods,
         repeat: bool,
     ) -> Option<(Command, InputExtra)> {
+        if self.capturing {
+            if repeat || MODIFIER_KEYS.contains(&key) {
+                return None;
+            }
+            return self.captured(Input::Key(key), mods);
+        }
         self.resolve(ctx, Input::Key(key), mods, InputExtra::RepeatedKey(repeat))
     }
+
+    pub fn text_input_event(
+        &mut self,
+        _ctx: &mut Context,
+        character: char,
+    ) -> Option<(Command, InputExtra)> {
+        if character.is_control() {
+            return None;
+        }
+        Some((Command::Text, InputExtra::Char(character)))
+    }
+}
+
+const MODIFIER_KEYS: [KeyCode; 8] = [
+    KeyCode::LShift,
+    KeyCode::RShift,
+    KeyCode::LControl,
+    KeyCode::RControl,
+    KeyCode::LAlt,
+    KeyCode::RAlt,
+    KeyCode::LWin,
+    KeyCode::RWin,
+];
+
+const KEYS: [KeyCode; 79] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Escape,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::Delete,
+    KeyCode::End,
+    KeyCode::PageDown,
+    KeyCode::PageUp,
+    KeyCode::Left,
+    KeyCode::Up,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::Back,
+    KeyCode::Return,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::Add,
+    KeyCode::Subtract,
+    KeyCode::Equals,
+    KeyCode::Minus,
+    KeyCode::Comma,
+    KeyCode::Period,
+];
+
+const MODIFIERS: [(KeyMods, &str); 4] = [
+    (KeyMods::CTRL, "Ctrl"),
+    (KeyMods::SHIFT, "Shift"),
+    (KeyMods::ALT, "Alt"),
+    (KeyMods::LOGO, "Logo"),
+];
+
+fn mouse_button_name(button: MouseButton) -> String {
+    match button {
+        MouseButton::Left => "Left".to_owned(),
+        MouseButton::Right => "Right".to_owned(),
+        MouseButton::Middle => "Middle".to_owned(),
+        MouseButton::Other(index) => format!("Button{}", index),
+    }
+}
+
+fn parse_mouse_button(name: &str) -> Option<MouseButton> {
+    match name {
+        "Left" => Some(MouseButton::Left),
+        "Right" => Some(MouseButton::Right),
+        "Middle" => Some(MouseButton::Middle),
+        other if other.starts_with("Button") => {
+            other["Button".len()..].parse().ok().map(MouseButton::Other)
+        }
+        _ => None,
+    }
+}
+
+pub fn binding_name(input: Input, keymods: KeyMods) -> String {
+    let mut name = MODIFIERS
+        .iter()
+        .filter(|(modifier, _)| keymods.contains(*modifier))
+        .map(|(_, modifier)| format!("{}+", modifier))
+        .collect::<String>();
+    name.push_str(&match input {
+        Input::Key(key) => format!("{:?}", key),
+        Input::Mouse(button) => format!("Mouse{}", mouse_button_name(button)),
+        Input::MouseDrag(button) => format!("Drag{}", mouse_button_name(button)),
+        Input::WheelUp => "WheelUp".to_owned(),
+        Input::WheelDown => "WheelDown".to_owned(),
+    });
+    name
+}
+
+pub fn parse_binding(name: &str) -> Option<(Input, KeyMods)> {
+    let mut parts = name.split('+').map(str::trim).collect::<Vec<_>>();
+    let input = match parts.pop()? {
+        "WheelUp" => Input::WheelUp,
+        "WheelDown" => Input::WheelDown,
+        other if other.starts_with("Mouse") => {
+            Input::Mouse(parse_mouse_button(&other["Mouse".len()..])?)
+        }
+        other if other.starts_with("Drag") => {
+            Input::MouseDrag(parse_mouse_button(&other["Drag".len()..])?)
+        }
+        other => Input::Key(
+            *KEYS
+                .iter()
+                .find(|key| format!("{:?}", key) == other)?,
+        ),
+    };
+    let mut keymods = KeyMods::NONE;
+    for part in parts {
+        let (modifier, _) = MODIFIERS.iter().find(|(_, modifier)| *modifier == part)?;
+        keymods |= *modifier;
+    }
+    Some((input, keymods))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebind_keeps_the_last_binding_of_essential_commands() {
+        let mut handler = InputHandler::default();
+        let left = Input::Mouse(MouseButton::Left);
+        assert_eq!(
+            handler.rebind(left, KeyMods::NONE, Command::Terraform),
+            Err(Command::Click)
+        );
+        assert_eq!(handler.bound(left, KeyMods::NONE), Some(Command::Click));
+
+        let extra = Input::Key(KeyCode::F11);
+        assert_eq!(handler.rebind(extra, KeyMods::NONE, Command::Click), Ok(None));
+        assert_eq!(handler.bindings_for(Command::Click), vec![(extra, KeyMods::NONE)]);
+        handler.bind(left, KeyMods::NONE, Command::Click);
+        assert_eq!(
+            handler.rebind(extra, KeyMods::NONE, Command::Terraform),
+            Ok(Some(Command::Click))
+        );
+        assert_eq!(handler.bindings_for(Command::Click), vec![(left, KeyMods::NONE)]);
+    }
 }
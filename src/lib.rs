@@ -0,0 +1,32 @@
+extern crate chrono;
+extern crate dirs;
+extern crate ggez;
+#[macro_use]
+extern crate log;
+extern crate nalgebra;
+extern crate noise;
+extern crate rand;
+extern crate ron;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate specs;
+#[macro_use]
+extern crate specs_derive;
+
+pub mod action;
+pub mod assets;
+pub mod bot;
+pub mod ecs;
+pub mod game;
+pub mod gui;
+pub mod headless;
+pub mod highscores;
+pub mod history;
+pub mod input;
+pub mod replay;
+pub mod saveload;
+pub mod state;
+pub mod time;
+pub mod undo;
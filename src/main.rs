@@ -2,31 +2,16 @@
 extern crate chrono;
 extern crate fern;
 extern crate ggez;
+extern crate its_not_cool;
 #[macro_use]
 extern crate log;
 extern crate nalgebra;
-extern crate noise;
-extern crate rand;
-extern crate ron;
-extern crate serde;
-#[macro_use]
-extern crate serde_derive;
-extern crate specs;
-#[macro_use]
-extern crate specs_derive;
 
 use ggez::conf::{WindowMode, WindowSetup};
 use ggez::event;
 use ggez::{ContextBuilder, GameResult};
 
-mod assets;
-mod ecs;
-mod game;
-mod gui;
-mod input;
-mod saveload;
-mod state;
-mod time;
+use its_not_cool::game;
 
 fn wrapped() -> GameResult {
     let w_dim = nalgebra::Vector2::new(640.0, 380.0);
@@ -0,0 +1,112 @@
+use chrono::Local;
+use ron;
+use specs::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use action::GameAction;
+use ecs::*;
+use saveload::{data_dir, SaveError, SaveResult};
+
+pub const REPLAY_VERSION: u32 = 1;
+const REPLAY_EXTENSION: &str = "ron";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u32,
+    pub settings: MapSettings,
+    pub actions: Vec<GameAction>,
+}
+
+impl Replay {
+    pub fn new(seed: u32, settings: MapSettings) -> Replay {
+        Replay {
+            version: REPLAY_VERSION,
+            seed,
+            settings,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> SaveResult<Replay> {
+        let source = fs::read_to_string(path)?;
+        let replay: Replay = ron::de::from_str(&source)
+            .map_err(|e| SaveError::Parse(format!("{}: {}", path.display(), e)))?;
+        if replay.version > REPLAY_VERSION {
+            return Err(SaveError::UnsupportedVersion(replay.version));
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> SaveResult {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let source = ron::ser::to_string_pretty(self, Default::default())
+            .map_err(|e| SaveError::Serialize(format!("{}: {}", path.display(), e)))?;
+        fs::write(path, source.as_bytes())?;
+        info!("Saved replay to {}", path.display());
+        Ok(())
+    }
+
+    pub fn save_timestamped(&self) -> SaveResult<PathBuf> {
+        let name = format!("replay-{}", Local::now().format("%Y%m%d-%H%M%S"));
+        let path = replay_dir().join(name).with_extension(REPLAY_EXTENSION);
+        self.save(&path)?;
+        Ok(path)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ActionLog(pub Option<Replay>);
+
+fn replay_dir() -> PathBuf {
+    data_dir().join("replays")
+}
+
+pub fn start_recording(world: &mut World, seed: u32, settings: MapSettings) {
+    world.add_resource(ActionLog(Some(Replay::new(seed, settings))));
+}
+
+pub fn record(world: &World, action: GameAction) {
+    if let Some(mut log) = world.res.try_fetch_mut::<ActionLog>() {
+        if let Some(ref mut replay) = log.0 {
+            replay.actions.push(action);
+        }
+    }
+}
+
+pub fn save_recorded(world: &World) {
+    let replay = match world.res.try_fetch::<ActionLog>() {
+        Some(log) => log.0.clone(),
+        None => None,
+    };
+    if let Some(replay) = replay {
+        if replay.actions.is_empty() {
+            return;
+        }
+        if let Err(e) = replay.save_timestamped() {
+            error!("Could not save replay: {:?}", e);
+        }
+    }
+}
+
+pub fn latest() -> Option<PathBuf> {
+    let entries = match fs::read_dir(replay_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return None,
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == REPLAY_EXTENSION))
+        .filter_map(|path| {
+            fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .map(|modified| (modified, path))
+        })
+        .max()
+        .map(|(_, path)| path)
+}
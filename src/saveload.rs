@@ -1,21 +1,396 @@
 use super::ecs::*;
+use chrono::{Local, TimeZone};
+use dirs;
+use ggez::GameError;
 use ron;
 use specs::error::NoError;
 use specs::prelude::*;
 use specs::saveload::{DeserializeComponents, SerializeComponents, U64Marker, U64MarkerAllocator};
-use std::fs::File;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
-pub fn save(world: &mut World) {
-    //SaveWorld(format!("{}/save", env!("CARGO_MANIFEST_DIR"))).run_now(&world.res);
-    SaveWorld("./save".to_owned()).run_now(&world.res);
+use history::History;
+use replay::{ActionLog, Replay};
+use time::Time;
+use undo::UndoStack;
+
+pub const SAVE_VERSION: u32 = 5;
+pub const QUICKSAVE_SLOT: &str = "quicksave";
+const HEADER_FILE: &str = "header.ron";
+const META_FILE: &str = "meta.ron";
+const RESOURCES_FILE: &str = "resources.ron";
+const ENTITIES_FILE: &str = "entities.ron";
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Serialize(String),
+    Parse(String),
+    UnsupportedVersion(u32),
+    EmptySlotName,
+    InvalidSlotName(String),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "I/O error: {}", e),
+            SaveError::Serialize(e) => write!(f, "Could not serialize save: {}", e),
+            SaveError::Parse(e) => write!(f, "Could not parse save: {}", e),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "Save format version {} is newer than the supported version {}",
+                version, SAVE_VERSION
+            ),
+            SaveError::EmptySlotName => write!(f, "Save slot name is empty"),
+            SaveError::InvalidSlotName(name) => write!(f, "Invalid save slot name {:?}", name),
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> SaveError {
+        SaveError::Io(e)
+    }
+}
+
+impl From<SaveError> for GameError {
+    fn from(e: SaveError) -> GameError {
+        GameError::ResourceLoadError(e.to_string())
+    }
 }
 
-pub fn load(world: &mut World) {
-    //LoadWorld(format!("{}/save", env!("CARGO_MANIFEST_DIR"))).run_now(&world.res);
-    SaveWorld("./save".to_owned()).run_now(&world.res);
+pub type SaveResult<T = ()> = Result<T, SaveError>;
+
+#[derive(Serialize, Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedResources {
+    seed: Seed,
+    grid: Grid,
+    time: Time,
+    #[serde(default)]
+    history: History,
+    #[serde(default)]
+    replay: Option<Replay>,
+    #[serde(default)]
+    settings: Option<MapSettings>,
 }
 
-struct SaveWorld(String);
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotInfo {
+    pub name: String,
+    pub turn: i32,
+    pub score: i32,
+    pub population: i32,
+    pub seed: u32,
+    pub timestamp: i64,
+}
+
+impl SlotInfo {
+    pub fn date(&self) -> String {
+        Local
+            .timestamp(self.timestamp, 0)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+}
+
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("its_not_cool")
+}
+
+fn saves_dir() -> PathBuf {
+    data_dir().join("saves")
+}
+
+fn slot_dir(name: &str) -> SaveResult<PathBuf> {
+    if name.is_empty() {
+        return Err(SaveError::EmptySlotName);
+    }
+    if sanitize_slot_name(name) != name {
+        return Err(SaveError::InvalidSlotName(name.to_owned()));
+    }
+    let dir = saves_dir().join(name);
+    if dir.parent() != Some(saves_dir().as_path()) {
+        return Err(SaveError::InvalidSlotName(name.to_owned()));
+    }
+    Ok(dir)
+}
+
+pub fn sanitize_slot_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+pub fn save(world: &mut World) -> SaveResult {
+    save_slot(world, QUICKSAVE_SLOT)
+}
+
+pub fn load(world: &mut World) -> SaveResult {
+    load_slot(world, QUICKSAVE_SLOT)
+}
+
+pub fn save_slot(world: &mut World, name: &str) -> SaveResult {
+    let name = sanitize_slot_name(name);
+    let dir = slot_dir(&name)?;
+    save_to(world, &dir)?;
+    let info = {
+        let time = world.read_resource::<Time>();
+        SlotInfo {
+            name,
+            turn: time.turn,
+            score: time.score,
+            population: time.population,
+            seed: world.read_resource::<Seed>().0,
+            timestamp: Local::now().timestamp(),
+        }
+    };
+    let info = ron::ser::to_string_pretty(&info, Default::default())
+        .map_err(|e| SaveError::Serialize(format!("{}: {}", META_FILE, e)))?;
+    fs::write(dir.join(META_FILE), info.as_bytes())?;
+    Ok(())
+}
+
+pub fn load_slot(world: &mut World, name: &str) -> SaveResult {
+    load_from(world, &slot_dir(name)?)
+}
+
+pub fn delete_slot(name: &str) -> SaveResult {
+    fs::remove_dir_all(slot_dir(name)?)?;
+    info!("Deleted save slot {}", name);
+    Ok(())
+}
+
+pub fn list_slots() -> Vec<SlotInfo> {
+    let mut slots = Vec::new();
+    let entries = match fs::read_dir(saves_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return slots,
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let name = match entry.file_name().into_string() {
+            Ok(ref name) if slot_dir(name).is_ok() => name.clone(),
+            _ => {
+                warn!("Skipping save directory {}", entry.path().display());
+                continue;
+            }
+        };
+        let path = entry.path().join(META_FILE);
+        match fs::read_to_string(&path) {
+            Ok(meta) => match ron::de::from_str::<SlotInfo>(&meta) {
+                Ok(info) => slots.push(SlotInfo { name, ..info }),
+                Err(e) => warn!("Skipping {}: {}", path.display(), e),
+            },
+            Err(e) => warn!("Skipping {}: {}", path.display(), e),
+        }
+    }
+    slots.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    slots
+}
+
+pub fn save_to(world: &mut World, dir: &Path) -> SaveResult {
+    let header = SaveHeader {
+        version: SAVE_VERSION,
+    };
+    let header = ron::ser::to_string_pretty(&header, Default::default())
+        .map_err(|e| SaveError::Serialize(format!("{}: {}", HEADER_FILE, e)))?;
+    let resources = SavedResources {
+        seed: *world.read_resource::<Seed>(),
+        grid: world.read_resource::<Grid>().clone(),
+        time: world.read_resource::<Time>().clone(),
+        history: world
+            .res
+            .try_fetch::<History>()
+            .map(|history| history.clone())
+            .unwrap_or_default(),
+        replay: world
+            .res
+            .try_fetch::<ActionLog>()
+            .and_then(|log| log.0.clone()),
+        settings: world.res.try_fetch::<MapSettings>().map(|settings| *settings),
+    };
+    let resources = ron::ser::to_string_pretty(&resources, Default::default())
+        .map_err(|e| SaveError::Serialize(format!("{}: {}", RESOURCES_FILE, e)))?;
+
+    let mut marker = MarkEntities;
+    <MarkEntities as System>::setup(&mut marker, &mut world.res);
+    marker.run_now(&world.res);
+    let mut saver = SaveWorld(Ok(String::new()));
+    <SaveWorld as System>::setup(&mut saver, &mut world.res);
+    saver.run_now(&world.res);
+    let entities = saver.0.map_err(SaveError::Serialize)?;
+
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(HEADER_FILE), header.as_bytes())?;
+    fs::write(dir.join(RESOURCES_FILE), resources.as_bytes())?;
+    fs::write(dir.join(ENTITIES_FILE), entities.as_bytes())?;
+    info!("Saved game to {}", dir.display());
+    Ok(())
+}
+
+pub fn load_from(world: &mut World, dir: &Path) -> SaveResult {
+    let version = match fs::read_to_string(dir.join(HEADER_FILE)) {
+        Ok(header) => {
+            ron::de::from_str::<SaveHeader>(&header)
+                .map_err(|e| SaveError::Parse(format!("{}: {}", HEADER_FILE, e)))?
+                .version
+        }
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(SaveError::Io(e)),
+    };
+    if version > SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    let mut resources: SavedResources =
+        ron::de::from_str(&fs::read_to_string(dir.join(RESOURCES_FILE))?)
+            .map_err(|e| SaveError::Parse(format!("{}: {}", RESOURCES_FILE, e)))?;
+    let tiles = load_entities(fs::read(dir.join(ENTITIES_FILE))?, &resources.grid)?;
+    migrate(version, &mut resources)?;
+    resources.grid.restore_center();
+    let settings = resources.settings.unwrap_or_else(|| {
+        warn!("Save has no map settings, restarting it will use default terrain");
+        let (width, height, depth) = resources.grid.dimensions();
+        MapSettings {
+            width,
+            height,
+            depth,
+            ..MapSettings::default()
+        }
+    });
+    if settings.dimensions() != resources.grid.dimensions() {
+        return Err(SaveError::Parse(format!(
+            "{}: map settings do not match the grid",
+            RESOURCES_FILE
+        )));
+    }
+
+    world.delete_all();
+    world.maintain();
+    world.add_resource(resources.seed);
+    world.add_resource(settings);
+    world.add_resource(resources.grid);
+    world.add_resource(resources.time);
+    world.add_resource(resources.history);
+    world.add_resource(ActionLog(resources.replay));
+    world.add_resource(UndoStack::default());
+    {
+        let entities = world.entities();
+        let mut positions = world.write_storage::<Position>();
+        let mut tile_storage = world.write_storage::<Tile>();
+        for (pos, tile) in tiles {
+            let entity = entities.create();
+            positions.insert(entity, pos).unwrap();
+            tile_storage.insert(entity, tile).unwrap();
+        }
+    }
+    world.maintain();
+    info!("Loaded game from {}", dir.display());
+    Ok(())
+}
+
+fn load_entities(entities: Vec<u8>, grid: &Grid) -> SaveResult<Vec<(Position, Tile)>> {
+    let mut scratch = World::new();
+    scratch.register::<Position>();
+    scratch.register::<Tile>();
+    let mut loader = LoadWorld(entities, Ok(()));
+    <LoadWorld as System>::setup(&mut loader, &mut scratch.res);
+    loader.run_now(&scratch.res);
+    scratch.maintain();
+    loader.1.map_err(SaveError::Parse)?;
+    let (w, h, d) = grid.dimensions();
+    let entities = scratch.entities();
+    let positions = scratch.read_storage::<Position>();
+    let tiles = scratch.read_storage::<Tile>();
+    let mut loaded = Vec::new();
+    for entity in (&*entities).join() {
+        match (positions.get(entity), tiles.get(entity)) {
+            (Some(pos), Some(tile)) if pos.x() < w && pos.y() < h && pos.z() < d => {
+                loaded.push((*pos, *tile))
+            }
+            (Some(pos), Some(_)) => {
+                return Err(SaveError::Parse(format!(
+                    "{}: tile at {} {} {} is outside the {}x{}x{} map",
+                    ENTITIES_FILE,
+                    pos.x(),
+                    pos.y(),
+                    pos.z(),
+                    w,
+                    h,
+                    d
+                )))
+            }
+            _ => {
+                return Err(SaveError::Parse(format!(
+                    "{}: entity {:?} is missing its position or tile",
+                    ENTITIES_FILE, entity
+                )))
+            }
+        }
+    }
+    if loaded.is_empty() {
+        return Err(SaveError::Parse(format!("{}: no tiles", ENTITIES_FILE)));
+    }
+    Ok(loaded)
+}
+
+fn migrate(version: u32, resources: &mut SavedResources) -> SaveResult {
+    for from in version..SAVE_VERSION {
+        info!("Migrating save from version {} to {}", from, from + 1);
+        match from {
+            // Saves written before the header existed share the version 1 layout.
+            0 => (),
+            // Version 2 tracks the peak population; older saves get the starting default.
+            1 => {
+                let time = &mut resources.time;
+                time.peak_population = time.peak_population.max(time.population);
+            }
+            // Version 3 adds the replay log. Older saves have none to convert: no-op.
+            2 => (),
+            // Version 4 adds the city center. No-op: load_from calls restore_center for every save.
+            3 => (),
+            // Version 5 stores the map settings; older saves can recover them from the replay.
+            4 => if resources.settings.is_none() {
+                resources.settings = resources.replay.as_ref().map(|replay| replay.settings);
+            },
+            _ => return Err(SaveError::UnsupportedVersion(version)),
+        }
+    }
+    Ok(())
+}
+
+struct MarkEntities;
+
+impl<'a> System<'a> for MarkEntities {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, U64MarkerAllocator>,
+        WriteStorage<'a, U64Marker>,
+        ReadStorage<'a, Position>,
+    );
+
+    fn run(&mut self, (entities, mut allocator, mut markers, positions): Self::SystemData) {
+        *allocator = U64MarkerAllocator::new();
+        for entity in (&*entities).join() {
+            markers.remove(entity);
+        }
+        for (entity, _) in (&*entities, &positions).join() {
+            allocator.mark(entity, &mut markers);
+        }
+    }
+}
+
+struct SaveWorld(Result<String, String>);
 
 impl<'a> System<'a> for SaveWorld {
     type SystemData = (
@@ -25,25 +400,21 @@ impl<'a> System<'a> for SaveWorld {
         ReadStorage<'a, Tile>,
     );
 
-    fn run(&mut self, (entities, markers, positions, sprites): Self::SystemData) {
+    fn run(&mut self, (entities, markers, positions, tiles): Self::SystemData) {
         let mut serializer = ron::ser::Serializer::new(Some(Default::default()), true);
-        SerializeComponents::<NoError, U64Marker>::serialize(
-            &(positions, sprites),
+        self.0 = match SerializeComponents::<NoError, U64Marker>::serialize(
+            &(positions, tiles),
             &entities,
             &markers,
             &mut serializer,
-        ).unwrap();
-        let save = serializer.into_output_string();
-        info!("{}", &save);
-        use std::io::Write;
-        File::create(&self.0)
-            .expect("Could not create save file.")
-            .write_all(save.as_bytes())
-            .expect("Could not write save file.");
+        ) {
+            Ok(_) => Ok(serializer.into_output_string()),
+            Err(e) => Err(format!("{}: {}", ENTITIES_FILE, e)),
+        };
     }
 }
 
-struct LoadWorld(String);
+struct LoadWorld(Vec<u8>, Result<(), String>);
 
 impl<'a> System<'a> for LoadWorld {
     type SystemData = (
@@ -56,29 +427,68 @@ impl<'a> System<'a> for LoadWorld {
 
     fn run(
         &mut self,
-        (entities, mut allocator, mut markers, positions, sprites): Self::SystemData,
+        (entities, mut allocator, mut markers, positions, tiles): Self::SystemData,
     ) {
-        let save = {
-            let mut file = match File::open(&self.0) {
-                Ok(file) => file,
-                Err(e) => {
-                    panic!("Could not open save file: {} ({})", self.0, e);
-                }
-            };
-            let mut save = Vec::new();
-            use std::io::Read;
-            file.read_to_end(&mut save).expect("Could not read file.");
-            save
+        *allocator = U64MarkerAllocator::new();
+        let mut deserializer = match ron::de::Deserializer::from_bytes(&self.0) {
+            Ok(deserializer) => deserializer,
+            Err(e) => {
+                self.1 = Err(format!("{}: {}", ENTITIES_FILE, e));
+                return;
+            }
         };
-
-        let mut deserializer = ron::de::Deserializer::from_bytes(&save).unwrap();
-
-        DeserializeComponents::<NoError, U64Marker>::deserialize(
-            &mut (positions, sprites),
+        if let Err(e) = DeserializeComponents::<NoError, U64Marker>::deserialize(
+            &mut (positions, tiles),
             &entities,
             &mut markers,
             &mut allocator,
             &mut deserializer,
-        ).unwrap();
+        ) {
+            self.1 = Err(format!("{}: {}", ENTITIES_FILE, e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resources(time: Time) -> SavedResources {
+        SavedResources {
+            seed: Seed(1),
+            grid: Grid::new(4, 4, 8),
+            time,
+            history: History::default(),
+            replay: None,
+            settings: None,
+        }
+    }
+
+    #[test]
+    fn migration_raises_peak_population_to_current() {
+        let mut time = Time::new();
+        time.population = 9;
+        let mut old = resources(time);
+        migrate(1, &mut old).unwrap();
+        assert_eq!(old.time.peak_population, 9);
+    }
+
+    #[test]
+    fn migration_recovers_map_settings_from_the_replay() {
+        let settings = mapgen::PRESETS[2].1;
+        let mut old = resources(Time::new());
+        old.replay = Some(Replay::new(1, settings));
+        migrate(4, &mut old).unwrap();
+        assert_eq!(old.settings, Some(settings));
+    }
+
+    #[test]
+    fn grid_without_dimensions_is_rejected() {
+        let grid = Grid::new(6, 5, 12);
+        let saved = ron::ser::to_string(&grid).unwrap();
+        assert_eq!(ron::de::from_str::<Grid>(&saved).unwrap().dimensions(), (6, 5, 12));
+        let truncated = saved.replace("dimensions:(6,5,12),", "");
+        assert_ne!(truncated, saved);
+        assert!(ron::de::from_str::<Grid>(&truncated).is_err());
     }
 }
@@ -0,0 +1,251 @@
+use super::*;
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Mesh, Rect};
+use input::{binding_name, Input, InputHandler};
+use nalgebra as na;
+
+const ROW_HEIGHT: f32 = 16.0;
+const ROWS_PER_COLUMN: usize = 18;
+
+const COMMANDS: [(Command, &str); 36] = [
+    (Command::Click, "Click"),
+    (Command::ContextMenu, "Build menu"),
+    (Command::Confirm, "Confirm"),
+    (Command::Erase, "Erase"),
+    (Command::QuickSave, "Quick save"),
+    (Command::QuickLoad, "Quick load"),
+    (Command::SaveMenu, "Save menu"),
+    (Command::LoadMenu, "Load menu"),
+    (Command::Undo, "Undo"),
+    (Command::Redo, "Redo"),
+    (Command::Pan, "Pan (drag)"),
+    (Command::PanLeft, "Pan left"),
+    (Command::PanRight, "Pan right"),
+    (Command::PanUp, "Pan up"),
+    (Command::PanDown, "Pan down"),
+    (Command::ZoomIn, "Zoom in"),
+    (Command::ZoomOut, "Zoom out"),
+    (Command::RotateLeft, "Rotate left"),
+    (Command::RotateRight, "Rotate right"),
+    (Command::CursorLeft, "Cursor left"),
+    (Command::CursorRight, "Cursor right"),
+    (Command::CursorUp, "Cursor up"),
+    (Command::CursorDown, "Cursor down"),
+    (Command::Hotkey(0), "Build 1"),
+    (Command::Hotkey(1), "Build 2"),
+    (Command::Hotkey(2), "Build 3"),
+    (Command::Hotkey(3), "Build 4"),
+    (Command::Hotkey(4), "Build 5"),
+    (Command::Hotkey(5), "Build 6"),
+    (Command::Hotkey(6), "Build 7"),
+    (Command::Hotkey(7), "Build 8"),
+    (Command::Hotkey(8), "Build 9"),
+    (Command::Terraform, "Terraform"),
+    (Command::Pause, "Pause / back"),
+    (Command::ExportHistory, "Export history"),
+    (Command::Quit, "Quit"),
+];
+
+fn label(command: Command) -> &'static str {
+    COMMANDS
+        .iter()
+        .find(|(other, _)| *other == command)
+        .map(|(_, label)| *label)
+        .unwrap_or("")
+}
+
+fn row_pos(i: usize) -> na::Point2<f32> {
+    na::Point2::new(
+        20.0 + (i / ROWS_PER_COLUMN) as f32 * 310.0,
+        30.0 + (i % ROWS_PER_COLUMN) as f32 * ROW_HEIGHT,
+    )
+}
+
+fn defaults_pos() -> na::Point2<f32> {
+    na::Point2::new(20.0, 345.0)
+}
+
+fn back_pos() -> na::Point2<f32> {
+    na::Point2::new(560.0, 345.0)
+}
+
+pub struct BindingsMenu {
+    rows: Vec<(Command, Text)>,
+    capturing: Option<Command>,
+    message: String,
+    defaults_text: Text,
+    back_text: Text,
+}
+
+impl BindingsMenu {
+    pub fn new() -> BindingsMenu {
+        BindingsMenu {
+            rows: Vec::new(),
+            capturing: None,
+            message: "Click a command to rebind it.".to_owned(),
+            defaults_text: Text::new("[defaults]"),
+            back_text: Text::new("[back]"),
+        }
+    }
+
+    fn refresh(&mut self, handler: &InputHandler) {
+        self.rows = COMMANDS
+            .iter()
+            .map(|(command, label)| {
+                let bound = handler
+                    .bindings_for(*command)
+                    .into_iter()
+                    .map(|(input, keymods)| binding_name(input, keymods))
+                    .collect::<Vec<_>>();
+                let bound = if bound.is_empty() {
+                    "-".to_owned()
+                } else {
+                    bound.join(", ")
+                };
+                (
+                    *command,
+                    Text::new(
+                        TextFragment::new(format!("{}: {}", label, bound))
+                            .scale(Scale::uniform(14.0)),
+                    ),
+                )
+            })
+            .collect();
+    }
+
+    fn rebind(&mut self, handler: &mut InputHandler, input: Input, keymods: KeyMods) {
+        let command = match self.capturing.take() {
+            Some(command) => command,
+            None => return,
+        };
+        if input == Input::Key(KeyCode::Escape) && keymods == KeyMods::NONE {
+            self.message = "Cancelled.".to_owned();
+            return;
+        }
+        let name = binding_name(input, keymods);
+        self.message = match handler.rebind(input, keymods, command) {
+            Ok(Some(other)) => format!(
+                "{} bound to {}; it was unbound from {}.",
+                name,
+                label(command),
+                label(other)
+            ),
+            Ok(None) => format!("{} bound to {}.", name, label(command)),
+            Err(other) => format!(
+                "{} is the only binding for {}; bind it elsewhere first.",
+                name,
+                label(other)
+            ),
+        };
+        self.refresh(handler);
+    }
+}
+
+impl State for BindingsMenu {
+    fn start(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+    ) -> GameResult {
+        let handler = _world.read_resource::<InputHandler>();
+        self.refresh(&handler);
+        Ok(())
+    }
+
+    fn stop(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
+        if let Err(e) = _world.read_resource::<InputHandler>().save() {
+            error!("Could not save key bindings: {}", e);
+        }
+        Ok(())
+    }
+
+    fn input(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+        _command: Command,
+        _extra: InputExtra,
+    ) -> GameResult<Transition> {
+        match _command {
+            Command::Captured => if let InputExtra::Input(input, keymods) = _extra {
+                let mut handler = _world.write_resource::<InputHandler>();
+                self.rebind(&mut handler, input, keymods);
+            },
+            Command::Pause => return Ok(Transition::Pop),
+            Command::Click => {
+                if gui::is_hovered(_ctx, &self.back_text, back_pos()) {
+                    return Ok(Transition::Pop);
+                }
+                let mut handler = _world.write_resource::<InputHandler>();
+                if gui::is_hovered(_ctx, &self.defaults_text, defaults_pos()) {
+                    *handler = InputHandler::default();
+                    self.message = "Restored default bindings.".to_owned();
+                    self.refresh(&handler);
+                    return Ok(Transition::None);
+                }
+                let clicked = self
+                    .rows
+                    .iter()
+                    .enumerate()
+                    .find(|(i, (_, text))| gui::is_hovered(_ctx, text, row_pos(*i)))
+                    .map(|(_, (command, _))| *command);
+                if let Some(command) = clicked {
+                    handler.capture();
+                    self.capturing = Some(command);
+                    self.message = format!(
+                        "Press a key or mouse button for {} (Escape cancels).",
+                        label(command)
+                    );
+                }
+            }
+            _ => (),
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
+        let rect = Mesh::new_rectangle(_ctx, DrawMode::Fill, Rect::new(0.0, 0.0, 640.0, 380.0))?;
+        graphics::draw(
+            _ctx,
+            &rect,
+            DrawParam::new().color(Color::new(0.0, 0.0, 0.0, 0.9)),
+        )?;
+        graphics::draw(
+            _ctx,
+            &Text::new("CONTROLS"),
+            DrawParam::new().dest(na::Point2::new(20.0, 5.0)),
+        )?;
+        for (i, (command, text)) in self.rows.iter().enumerate() {
+            if self.capturing == Some(*command) {
+                graphics::draw(
+                    _ctx,
+                    text,
+                    DrawParam::new()
+                        .dest(row_pos(i))
+                        .color(Color::new(1.0, 1.0, 0.3, 1.0)),
+                )?;
+            } else {
+                gui::draw_button(_ctx, text, row_pos(i))?;
+            }
+        }
+        graphics::draw(
+            _ctx,
+            &Text::new(TextFragment::new(self.message.as_str()).scale(Scale::uniform(15.0))),
+            DrawParam::new().dest(na::Point2::new(20.0, 325.0)),
+        )?;
+        gui::draw_button(_ctx, &self.defaults_text, defaults_pos())?;
+        gui::draw_button(_ctx, &self.back_text, back_pos())
+    }
+
+    fn draw_underlying(&self) -> bool {
+        true
+    }
+}
+
+impl Display for BindingsMenu {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "Bindings Menu")
+    }
+}
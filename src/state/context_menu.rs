@@ -1,17 +1,19 @@
 use super::*;
+use action::{self, GameAction, PlacementError};
 use nalgebra as na;
 use specs::world::Index;
 use std::f32::INFINITY;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MenuOption {
+    Build(Structure),
+    Terraform,
+}
+
 pub struct ContextMenu {
     is_top: bool,
-    target_entity: Entity,
-    target_tile: Tile,
     target_pos: Position,
-    near_city: bool,
-    in_water: bool,
-    on_shore: bool,
-    options: Vec<(na::Vector2<f32>, SpriteHandle)>,
+    options: Vec<(na::Vector2<f32>, MenuOption, Vec<PlacementError>)>,
 }
 
 impl ContextMenu {
@@ -20,78 +22,90 @@ impl ContextMenu {
         world: &'c mut World,
         assets: &mut Assets,
     ) -> Option<ContextMenu> {
-        let grid = world.read_resource::<Grid>();
-        let entities = world.entities();
-        let positions = world.read_storage::<Position>();
-        let tiles = world.read_storage::<Tile>();
-        let target = (&*entities, &positions, &tiles)
-            .join()
-            .find(|(entity, pos, tile)| {
-                grid.is_top_tile(pos) && tile::hit_test(ctx, tile::map_pos_to_screen(pos))
-            });
+        let picked = {
+            let grid = world.read_resource::<Grid>();
+            let camera = world.read_resource::<Camera>();
+            tile::pick_mouse(ctx, &grid, &camera)
+        };
+        match picked {
+            Some((x, y, _)) => ContextMenu::at(world, assets, x, y),
+            None => None,
+        }
+    }
+
+    pub fn at<'c>(
+        world: &'c mut World,
+        assets: &mut Assets,
+        x: usize,
+        y: usize,
+    ) -> Option<ContextMenu> {
+        let target = construction::top_tile(world, x, y);
         if let Some((entity, pos, tile)) = target {
-            let in_water = *tile == Tile::Water;
-            let (w, h, d) = grid.dimensions();
-            let mut on_shore = false;
-            for (other_pos, other_tile) in (&positions, &tiles).join().filter(|(p, _)| {
-                (p.y() == pos.y()
-                    && (p.x() > 0 && p.x() - 1 <= pos.x())
-                    && (p.x() < w && p.x() + 1 >= pos.x()))
-                    || (p.x() == pos.x()
-                        && (p.y() > 0 && p.y() - 1 <= pos.y())
-                        && (p.y() < h && p.y() + 1 >= pos.y()))
-            }) {
-                if *other_tile == Tile::Water {
-                    on_shore = true;
-                    break;
-                }
-            }
             debug!("Target: {:?} at {:?} ({:?})", tile, pos, entity);
-            let civ = grid.is_civilizable(pos.x(), pos.y());
-            let options = vec![
-                (
-                    na::Vector2::new(-3.3 * TILE_SIZE.0, 0.5 * TILE_SIZE.0),
-                    SpriteHandle::Housing,
-                ),
-                (
-                    na::Vector2::new(-1.1 * TILE_SIZE.0, 0.5 * TILE_SIZE.0),
-                    SpriteHandle::Powerplant,
-                ),
-                (
-                    na::Vector2::new(1.1 * TILE_SIZE.0, 0.5 * TILE_SIZE.0),
-                    SpriteHandle::Fishery,
-                ),
-                (
-                    na::Vector2::new(3.3 * TILE_SIZE.0, 0.5 * TILE_SIZE.0),
-                    SpriteHandle::Farm,
-                ),
-                (
-                    na::Vector2::new(-2.2 * TILE_SIZE.0, 1.5 * TILE_SIZE.0),
-                    SpriteHandle::Sanctuary,
-                ),
-                (
-                    na::Vector2::new(0.0 * TILE_SIZE.0, 1.5 * TILE_SIZE.0),
-                    SpriteHandle::Terraform,
-                ),
-                (
-                    na::Vector2::new(2.2 * TILE_SIZE.0, 1.5 * TILE_SIZE.0),
-                    SpriteHandle::Renewables,
-                ),
-            ];
+            let mut options = world
+                .read_resource::<StructureDefs>()
+                .iter()
+                .map(|def| {
+                    (
+                        na::Vector2::new(
+                            def.menu_position.0 * TILE_SIZE.0,
+                            def.menu_position.1 * TILE_SIZE.0,
+                        ),
+                        MenuOption::Build(def.structure),
+                    )
+                })
+                .collect::<Vec<_>>();
+            options.push((
+                na::Vector2::new(0.0 * TILE_SIZE.0, 1.5 * TILE_SIZE.0),
+                MenuOption::Terraform,
+            ));
+            let options = options
+                .into_iter()
+                .map(|(vec, option)| {
+                    let action = ContextMenu::action_at(world, &pos, option);
+                    (vec, option, action::check(world, &action).err().unwrap_or_default())
+                })
+                .collect();
             assets.fetch_sound(SoundHandle::Click).play();
             return Some(ContextMenu {
                 is_top: false,
-                target_entity: entity,
-                target_tile: *tile,
-                target_pos: *pos,
-                near_city: civ,
-                in_water,
-                on_shore,
+                target_pos: pos,
                 options,
             });
         }
         None
     }
+
+    fn common_errors(&self) -> Vec<PlacementError> {
+        let mut options = self.options.iter();
+        let mut common = match options.next() {
+            Some((_, _, errors)) => errors.clone(),
+            None => return Vec::new(),
+        };
+        for (_, _, errors) in options {
+            common.retain(|error| errors.contains(error));
+        }
+        common
+    }
+
+    fn action_at(world: &World, pos: &Position, option: MenuOption) -> GameAction {
+        let (x, y) = (pos.x(), pos.y());
+        match option {
+            MenuOption::Build(structure) => GameAction::Build(x, y, structure),
+            MenuOption::Terraform => action::terraform(world, x, y),
+        }
+    }
+}
+
+fn error_text(errors: &[PlacementError]) -> Text {
+    let mut text = Text::new("");
+    for (i, error) in errors.iter().enumerate() {
+        if i > 0 {
+            text.add(" ");
+        }
+        text.add(TextFragment::new(error.to_string()).color(gui::RED));
+    }
+    text
 }
 
 impl State for ContextMenu {
@@ -138,212 +152,118 @@ impl State for ContextMenu {
         _command: Command,
         _extra: InputExtra,
     ) -> GameResult<Transition> {
-        let mut reflood = false;
-        if _command == Command::Click {
-            let mut i = 0;
-            let mut new_tile = None;
-            let mut pick_or_place = false;
-            let mut grid = _world.write_resource::<Grid>();
-            let mut time = _world.write_resource::<Time>();
-            for (vec, sprite) in &self.options {
-                if self.near_city
-                    && !self.in_water
-                    && tile::hit_test(_ctx, tile::map_pos_to_screen(&self.target_pos) + vec)
-                {
-                    new_tile = match i {
-                        0 => Some(Tile::Structure(Structure::Housing)),
-                        1 => Some(Tile::Structure(Structure::Powerplant)),
-                        2 => if self.on_shore {
-                            Some(Tile::Structure(Structure::Fishery))
-                        } else {
-                            None
-                        },
-                        3 => Some(Tile::Structure(Structure::Farm)),
-                        4 => Some(Tile::Structure(Structure::Sanctuary)),
-                        5 => {
-                            pick_or_place = true;
-                            grid.held_tile
-                        }
-                        6 => Some(Tile::Structure(Structure::Renewables)),
-                        _ => panic!("that shouldn't happen"),
-                    };
-                    break;
-                }
-                i += 1;
+        let chosen = match _command {
+            Command::Click => {
+                let grid = _world.read_resource::<Grid>();
+                let camera = _world.read_resource::<Camera>();
+                let pos = tile::map_pos_to_screen(&self.target_pos, grid.dimensions(), &camera);
+                self.options
+                    .iter()
+                    .find(|(vec, _, _)| tile::hit_test(_ctx, &camera, pos + vec * camera.zoom))
+                    .map(|(_, option, _)| *option)
             }
-            if let Some(new_tile) = new_tile {
-                let entities = _world.entities();
-                let mut positions = _world.write_storage::<Position>();
-                let mut tiles = _world.write_storage::<Tile>();
-                let mut modify = false;
-                let mut place = false;
-                for (entity, pos, tile) in (&*entities, &mut positions, &mut tiles).join() {
-                    if *pos == self.target_pos {
-                        match tile {
-                            Tile::Trees => modify = true,
-                            Tile::Terrain => place = true,
-                            _ => {}
-                        }
-                    }
-                }
-                if modify {
-                    _assets.fetch_sound(SoundHandle::Construct).play();
-                    time.turn_passed = true;
-                    if pick_or_place {
-                        grid.held_tile = None;
-                    }
-                    debug!(
-                        "replacing {} {} {}",
-                        self.target_pos.x(),
-                        self.target_pos.y(),
-                        self.target_pos.z()
-                    );
-                    *tiles.get_mut(self.target_entity).unwrap() = new_tile;
-                    grid.new_position(
-                        new_tile,
-                        self.target_pos.x(),
-                        self.target_pos.y(),
-                        self.target_pos.z(),
-                    );
-                } else if place {
-                    _assets.fetch_sound(SoundHandle::Construct).play();
-                    time.turn_passed = true;
-                    if pick_or_place {
-                        grid.held_tile = None;
-                    }
-                    let entity = entities.create();
-                    positions
-                        .insert(
-                            entity,
-                            grid.new_position(
-                                new_tile,
-                                self.target_pos.x(),
-                                self.target_pos.y(),
-                                self.target_pos.z() + 1,
-                            ),
-                        )
-                        .unwrap();
-                    tiles.insert(entity, new_tile).unwrap();
-                }
-            } else if pick_or_place {
+            Command::Hotkey(index) => _world
+                .read_resource::<StructureDefs>()
+                .iter()
+                .nth(index)
+                .map(|def| MenuOption::Build(def.structure)),
+            Command::Terraform => Some(MenuOption::Terraform),
+            _ => None,
+        };
+        if let Some(option) = chosen {
+            let action = ContextMenu::action_at(_world, &self.target_pos, option);
+            if action::apply(_world, &action) {
                 _assets.fetch_sound(SoundHandle::Construct).play();
-                grid.held_tile = Some(self.target_tile);
-                debug!(
-                    "removing {} {} {}",
-                    self.target_pos.x(),
-                    self.target_pos.y(),
-                    self.target_pos.z()
-                );
-                _world.entities().delete(self.target_entity);
-                grid.uncivilize(self.target_pos.x(), self.target_pos.y());
-                grid.lower_heightmap(self.target_pos.x(), self.target_pos.y());
-                grid.new_position(
-                    Tile::Terrain,
-                    self.target_pos.x(),
-                    self.target_pos.y(),
-                    self.target_pos.z() - 1,
-                );
-                reflood = true;
             }
         }
-        if reflood {
-            _world.write_resource::<Grid>().current_sealevel -= 1;
-            mapgen::Flood.run_now(&mut _world.res);
-        }
         Ok(Transition::Pop)
     }
 
     fn draw(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
-        let pos = tile::map_pos_to_screen(&self.target_pos);
+        let camera = *_world.read_resource::<Camera>();
+        let pos = tile::map_pos_to_screen(
+            &self.target_pos,
+            _world.read_resource::<Grid>().dimensions(),
+            &camera,
+        );
         graphics::draw(
             _ctx,
             _assets.fetch_mesh(MeshHandle::TileSelector),
-            DrawParam::new().dest(pos).color(graphics::BLACK),
+            DrawParam::new()
+                .dest(pos)
+                .color(graphics::BLACK)
+                .scale(camera.scale()),
         )?;
-        let mut i = 0;
         let mut tooltip_drawn = false;
-        for (vec, sprite) in &self.options {
+        let defs = _world.read_resource::<StructureDefs>();
+        for (vec, option, errors) in &self.options {
+            let vec = vec * camera.zoom;
+            let sprite = match option {
+                MenuOption::Build(structure) => defs.get(*structure).sprite,
+                MenuOption::Terraform => SpriteHandle::Terraform,
+            };
             graphics::draw(
                 _ctx,
                 _assets.fetch_mesh(MeshHandle::Tile),
                 DrawParam::new()
-                    .dest(pos + vec + na::Vector2::new(0.0, 0.25 * TILE_SIZE.1))
+                    .dest(pos + vec + na::Vector2::new(0.0, 0.25 * TILE_SIZE.1) * camera.zoom)
                     .color(Color::new(0.0, 0.0, 0.0, 0.95))
-                    .scale(na::Vector2::new(TILE_SIZE.0, TILE_SIZE.1)),
+                    .scale(na::Vector2::new(TILE_SIZE.0, TILE_SIZE.1) * camera.zoom),
             )?;
             graphics::draw(
                 _ctx,
-                _assets.fetch_sprite(*sprite),
+                _assets.fetch_sprite(sprite),
                 DrawParam::new()
-                    .dest(pos + vec + na::Vector2::new(-TILE_SIZE.0, -0.5 * TILE_SIZE.1)),
+                    .dest(
+                        pos + vec + na::Vector2::new(-TILE_SIZE.0, -0.5 * TILE_SIZE.1) * camera.zoom,
+                    )
+                    .color(if errors.is_empty() {
+                        graphics::WHITE
+                    } else {
+                        Color::new(0.4, 0.4, 0.4, 0.6)
+                    })
+                    .scale(camera.scale()),
             )?;
-            if self.near_city && !self.in_water && !tooltip_drawn && tile::hit_test(_ctx, pos + vec)
-            {
+            if !tooltip_drawn && tile::hit_test(_ctx, &camera, pos + vec) {
                 graphics::draw(
                     _ctx,
                     _assets.fetch_mesh(MeshHandle::TileSelector),
-                    DrawParam::new().dest(pos + vec).color(random_color()),
+                    DrawParam::new()
+                        .dest(pos + vec)
+                        .color(if errors.is_empty() {
+                            random_color()
+                        } else {
+                            gui::RED
+                        })
+                        .scale(camera.scale()),
                 )?;
                 tooltip_drawn = true;
-                let mut grid = _world.write_resource::<Grid>();
-                let mut text = Text::new(TextFragment::new(match i {
-                    0 => "Build ",
-                    1 => "Build a ",
-                    2 => if self.on_shore {
-                        "Build a "
-                    } else {
-                        "Needs to be on shore!"
-                    },
-                    3 => "Build a ",
-                    4 => "Build a ",
-                    5 => if grid.held_tile != None {
-                        "Place here"
-                    } else {
-                        "Pick up"
-                    },
-                    6 => "Build ",
-                    _ => panic!("that shouldn't happen"),
-                }));
-                text.add(
-                    TextFragment::new(match i {
-                        0 => "Housing",
-                        1 => "Power Plant",
-                        2 => if self.on_shore {
-                            "Fishing Pier"
+                let text = match option {
+                    _ if !errors.is_empty() => error_text(errors),
+                    MenuOption::Build(structure) => {
+                        let def = defs.get(*structure);
+                        let mut text = Text::new(format!("Build {}", def.article));
+                        text.add(
+                            TextFragment::new(def.name.as_str())
+                                .color(Color::new(0.1, 0.6, 0.6, 1.0)),
+                        );
+                        text.add(format!(" ({})", def.effects()));
+                        text
+                    }
+                    MenuOption::Terraform => {
+                        if _world.read_resource::<Grid>().held_tile != None {
+                            Text::new("Place here")
                         } else {
-                            ""
-                        },
-                        3 => "Farm",
-                        4 => "Polar Bear Sanctuary",
-                        5 => "",
-                        6 => "Eco Power Generators",
-                        _ => panic!("that shouldn't happen"),
-                    }).color(Color::new(0.1, 0.6, 0.6, 1.0)),
-                );
-                text.add(TextFragment::new(match i {
-                    0 => " (+1 housing, -1 power)",
-                    1 => " (+3 power, -1 nature)",
-                    2 => if self.on_shore {
-                        " (+3 food)"
-                    } else {
-                        ""
-                    },
-                    3 => " (+2 food)",
-                    4 => " (+1 nature, -1 power)",
-                    5 => "",
-                    6 => " (+2 power)",
-                    _ => panic!("that shouldn't happen"),
-                }));
-                gui::draw_tooltip(_ctx, pos, &text);
+                            Text::new("Pick up")
+                        }
+                    }
+                };
+                gui::draw_tooltip(_ctx, pos, &text)?;
             }
-            i += 1;
         }
-        if !self.near_city {
-            let text = Text::new("Too far from city!");
-            gui::draw_tooltip(_ctx, pos, &text);
-        } else if self.in_water {
-            let text = Text::new("Can't build on water!");
-            gui::draw_tooltip(_ctx, pos, &text);
+        let common = self.common_errors();
+        if !tooltip_drawn && !common.is_empty() {
+            gui::draw_tooltip(_ctx, pos, &error_text(&common))?;
         }
         Ok(())
     }
@@ -1,4 +1,6 @@
 use super::*;
+use action::{self, GameAction};
+use history::History;
 use ggez::input::mouse;
 use nalgebra as na;
 use std::f32::INFINITY;
@@ -7,23 +9,22 @@ pub struct Game<'a, 'b> {
     logic: Dispatcher<'a, 'b>,
     animation: Dispatcher<'a, 'b>,
     is_top: bool,
+    cursor: Option<(usize, usize)>,
     skip_text: Text,
     rules_text: Text,
 }
 
 impl<'a, 'b> Game<'a, 'b> {
     pub fn new<'c>(world: &'c mut World) -> Game<'a, 'b> {
+        world.add_resource(UndoStack::default());
+        world.add_resource(Camera::default());
+
         let mut logic = DispatcherBuilder::new().build();
         logic.setup(&mut world.res);
 
         let mut animation = DispatcherBuilder::new().build();
         animation.setup(&mut world.res);
 
-        let mut grid_populator = mapgen::GenerateMap;
-        <mapgen::GenerateMap as System>::setup(&mut grid_populator, &mut world.res);
-        grid_populator.run_now(&mut world.res);
-        world.maintain();
-
         let mut skip_text = Text::new("[skip turn]");
         let mut rules_text = Text::new(
             TextFragment::new(
@@ -39,10 +40,72 @@ impl<'a, 'b> Game<'a, 'b> {
             logic,
             animation,
             is_top: false,
+            cursor: None,
             skip_text,
             rules_text,
         }
     }
+
+    fn move_cursor(&mut self, world: &World, dx: i32, dy: i32) {
+        let grid = world.read_resource::<Grid>();
+        let camera = world.read_resource::<Camera>();
+        let dimensions = grid.dimensions();
+        let (w, h, _) = camera.view_dimensions(dimensions);
+        let (x, y) = match self.cursor {
+            Some(cursor) => {
+                let (x, y) = camera.view_coords(cursor, dimensions);
+                (
+                    (x as i32 + dx).max(0).min(w as i32 - 1) as usize,
+                    (y as i32 + dy).max(0).min(h as i32 - 1) as usize,
+                )
+            }
+            None => (w / 2, h / 2),
+        };
+        self.cursor = Some(camera.grid_coords((x, y), dimensions));
+    }
+
+    fn cursor_action(
+        &mut self,
+        ctx: &mut Context,
+        assets: &mut Assets,
+        world: &mut World,
+        command: Command,
+    ) -> GameResult {
+        if let Some((x, y)) = self.cursor {
+            let action = match command {
+                Command::Hotkey(index) => world
+                    .read_resource::<StructureDefs>()
+                    .iter()
+                    .nth(index)
+                    .map(|def| GameAction::Build(x, y, def.structure)),
+                _ => Some(action::terraform(world, x, y)),
+            };
+            if let Some(action) = action {
+                if action::apply(world, &action) {
+                    assets.fetch_sound(SoundHandle::Construct).play();
+                }
+            }
+        }
+        if world.read_resource::<Time>().turn_passed {
+            self.resume(ctx, assets, world)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn draw_map(ctx: &mut Context, assets: &mut Assets, world: &mut World) -> GameResult {
+    let grid = world.read_resource::<Grid>();
+    let positions = world.read_storage::<Position>();
+    let tiles = world.read_storage::<Tile>();
+    let defs = world.read_resource::<StructureDefs>();
+    let camera = world.read_resource::<Camera>();
+    let view = MapView::new(&grid, &camera);
+    let mut sorted = (&positions, &tiles).join().collect::<Vec<_>>();
+    sorted.sort_by_key(|(pos, _)| camera.draw_order(pos, view.dimensions));
+    for (pos, tile) in sorted.iter() {
+        tile.draw(ctx, assets, &defs, pos, &view)?;
+    }
+    Ok(())
 }
 
 impl<'a, 'b> State for Game<'a, 'b> {
@@ -79,14 +142,8 @@ impl<'a, 'b> State for Game<'a, 'b> {
     ) -> GameResult {
         self.is_top = true;
         let passed = _world.read_resource::<Time>().turn_passed;
-        if passed {
-            let flood = _world.read_resource::<Time>().flood_timer;
-            if flood < 1 {
-                _assets.fetch_sound(SoundHandle::WaveCrash).play();
-                mapgen::Flood.run_now(&mut _world.res);
-                _world.write_resource::<Time>().flood_timer = 8;
-            }
-            AllThingsDoer.run_now(&mut _world.res);
+        if passed && end_turn(_world) {
+            _assets.fetch_sound(SoundHandle::WaveCrash).play();
         }
         _world.write_resource::<Time>().turn_passed = false;
         Ok(())
@@ -113,11 +170,77 @@ impl<'a, 'b> State for Game<'a, 'b> {
                     if ((260.0 - x as f32).abs() as u32) < self.skip_text.width(_ctx)
                         && ((5.0 - y as f32).abs() as u32) < self.skip_text.height(_ctx)
                     {
-                        _world.write_resource::<Time>().turn_passed = true;
-                        self.resume(_ctx, _assets, _world);
+                        action::apply(_world, &GameAction::SkipTurn);
+                        self.resume(_ctx, _assets, _world)?;
                     }
                 }
             },
+            Command::Pause => return Ok(Transition::Push(Box::new(PauseMenu::new()))),
+            Command::SaveMenu => return Ok(Transition::Push(Box::new(SlotMenu::save()))),
+            Command::LoadMenu => return Ok(Transition::Push(Box::new(SlotMenu::load(true)))),
+            Command::Undo => if undo::undo(_world) {
+                _assets.fetch_sound(SoundHandle::Click).play();
+            },
+            Command::Redo => if undo::redo(_world) {
+                _assets.fetch_sound(SoundHandle::Click).play();
+            },
+            Command::Pan => if let InputExtra::XY(dx, dy) = _extra {
+                _world
+                    .write_resource::<Camera>()
+                    .pan_by(na::Vector2::new(dx as f32, dy as f32));
+            },
+            Command::PanLeft => _world
+                .write_resource::<Camera>()
+                .pan_by(na::Vector2::new(camera::PAN_STEP, 0.0)),
+            Command::PanRight => _world
+                .write_resource::<Camera>()
+                .pan_by(na::Vector2::new(-camera::PAN_STEP, 0.0)),
+            Command::PanUp => _world
+                .write_resource::<Camera>()
+                .pan_by(na::Vector2::new(0.0, camera::PAN_STEP)),
+            Command::PanDown => _world
+                .write_resource::<Camera>()
+                .pan_by(na::Vector2::new(0.0, -camera::PAN_STEP)),
+            Command::RotateLeft => _world.write_resource::<Camera>().rotate_by(-1),
+            Command::RotateRight => _world.write_resource::<Camera>().rotate_by(1),
+            Command::ZoomIn | Command::ZoomOut => {
+                let steps = if _command == Command::ZoomIn { 1 } else { -1 };
+                let mut camera = _world.write_resource::<Camera>();
+                match _extra {
+                    InputExtra::XY(x, y) => {
+                        camera.zoom_by(steps, na::Point2::new(x as f32, y as f32))
+                    }
+                    _ => camera.zoom_at_center(steps),
+                }
+            }
+            Command::CursorLeft => self.move_cursor(_world, -1, 0),
+            Command::CursorRight => self.move_cursor(_world, 1, 0),
+            Command::CursorUp => self.move_cursor(_world, 0, 1),
+            Command::CursorDown => self.move_cursor(_world, 0, -1),
+            Command::Confirm => if let Some((x, y)) = self.cursor {
+                if let Some(menu) = ContextMenu::at(_world, _assets, x, y) {
+                    return Ok(Transition::Push(Box::new(menu)));
+                }
+            },
+            Command::Hotkey(_) | Command::Terraform => {
+                self.cursor_action(_ctx, _assets, _world, _command)?
+            }
+            Command::ExportHistory => {
+                let history = _world
+                    .res
+                    .entry::<History>()
+                    .or_insert_with(History::default)
+                    .clone();
+                if let Err(e) = history.export_timestamped() {
+                    error!("History export failed: {:?}", e);
+                }
+            }
+            Command::QuickSave => if let Err(e) = saveload::save(_world) {
+                error!("Quick-save failed: {:?}", e);
+            },
+            Command::QuickLoad => if let Err(e) = saveload::load(_world) {
+                error!("Quick-load failed: {:?}", e);
+            },
             _ => (),
         }
         Ok(Transition::None)
@@ -131,9 +254,7 @@ impl<'a, 'b> State for Game<'a, 'b> {
     ) -> GameResult<Transition> {
         let over = _world.read_resource::<Time>().game_over;
         if over {
-            Ok(Transition::Push(Box::new(super::GameOver(
-                _world.read_resource::<Time>().score,
-            ))))
+            Ok(Transition::Push(Box::new(super::GameOver::new(_world))))
         } else if _world.read_resource::<Time>().game_over_transition_done {
             Ok(Transition::Pop)
         } else {
@@ -143,25 +264,33 @@ impl<'a, 'b> State for Game<'a, 'b> {
 
     fn draw(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
         self.animation.dispatch(&mut _world.res);
+        draw_map(_ctx, _assets, _world)?;
         let time = _world.read_resource::<Time>();
         let grid = _world.read_resource::<Grid>();
-        let positions = _world.read_storage::<Position>();
-        let tiles = _world.read_storage::<Tile>();
-        let sealevel = grid.current_sealevel;
-        let depth = grid.dimensions().2;
-        let mut sorted = (&positions, &tiles).join().collect::<Vec<_>>();
-        sorted.sort_by_key(|(&pos, _)| pos);
-        for (pos, tile) in sorted.iter() {
-            tile.draw(_ctx, _assets, pos, sealevel, depth, grid.is_top_tile(pos))?;
-        }
+        let defs = _world.read_resource::<StructureDefs>();
+        let camera = _world.read_resource::<Camera>();
+        let dimensions = grid.dimensions();
         if self.is_top {
-            for (pos, tile) in (&positions, &tiles).join() {
-                if grid.is_top_tile(pos) && tile.draw_tooltip(_ctx, _assets, pos)? {
-                    break;
+            if let Some((x, y, _)) = tile::pick_mouse(_ctx, &grid, &camera) {
+                if let Some((_, pos, tile)) = construction::top_tile(_world, x, y) {
+                    let view = MapView::new(&grid, &camera);
+                    tile.draw_tooltip(_ctx, _assets, &defs, &pos, &view)?;
+                }
+            }
+            if let Some((x, y)) = self.cursor {
+                if let Some((_, pos, _)) = construction::top_tile(_world, x, y) {
+                    graphics::draw(
+                        _ctx,
+                        _assets.fetch_mesh(MeshHandle::TileSelector),
+                        DrawParam::new()
+                            .dest(tile::map_pos_to_screen(&pos, dimensions, &camera))
+                            .color(graphics::WHITE)
+                            .scale(camera.scale()),
+                    )?;
                 }
             }
         }
-        gui::draw_score(_ctx, &time)?;
+        gui::draw_score(_ctx, &time, _world.read_resource::<Seed>().0)?;
         let offset = self.skip_text.width(_ctx) as f32;
         let mpos = mouse::get_position(_ctx);
         let color = if ((260.0 - mpos.x as f32).abs() as u32) < self.skip_text.width(_ctx)
@@ -1,10 +1,47 @@
 use super::*;
 use ggez::graphics::{DrawMode, Mesh, Rect};
-use ggez::input::mouse;
+use highscores::{self, HighScore, MAX_ENTRIES};
+use history::History;
 use nalgebra as na;
 use std::f32::INFINITY;
 
-pub struct GameOver(pub i32);
+const GRAPH_SIZE: (f32, f32) = (190.0, 105.0);
+
+fn graph_area(i: usize) -> Rect {
+    Rect::new(
+        20.0 + (i % 3) as f32 * (GRAPH_SIZE.0 + 15.0),
+        100.0 + (i / 3) as f32 * (GRAPH_SIZE.1 + 10.0),
+        GRAPH_SIZE.0,
+        GRAPH_SIZE.1,
+    )
+}
+
+pub struct GameOver {
+    score: i32,
+    rank: Option<usize>,
+    turns: i32,
+    peak_population: i32,
+    history: History,
+}
+
+impl GameOver {
+    pub fn new(world: &World) -> GameOver {
+        let time = world.read_resource::<Time>();
+        let entry = HighScore::new(&time, world.read_resource::<Seed>().0);
+        replay::save_recorded(world);
+        GameOver {
+            score: entry.score,
+            rank: highscores::record(entry),
+            turns: time.turn,
+            peak_population: time.peak_population,
+            history: world
+                .res
+                .try_fetch::<History>()
+                .map(|history| history.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
 
 impl State for GameOver {
     fn draw(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
@@ -12,15 +49,77 @@ impl State for GameOver {
         graphics::draw(
             _ctx,
             &rect,
-            DrawParam::new().color(Color::new(0.0, 0.0, 0.0, 0.3)),
+            DrawParam::new().color(Color::new(0.0, 0.0, 0.0, 0.85)),
         )?;
         let mut text = Text::new(TextFragment::new("GAME OVER\n\r").scale(Scale::uniform(30.0)));
-        text.add(TextFragment::new("CLICK TO RESTART"));
+        text.add(TextFragment::new(format!("Final score: {}  ", self.score)));
+        text.add(match self.rank {
+            Some(0) => {
+                TextFragment::new("New high score!\n\r").color(Color::new(1.0, 0.9, 0.2, 1.0))
+            }
+            Some(rank) => TextFragment::new(format!(
+                "Ranked #{} on the high score table\n\r",
+                rank + 1
+            )),
+            None => TextFragment::new(format!("Not in the top {}\n\r", MAX_ENTRIES)),
+        });
+        text.add(
+            TextFragment::new(format!(
+                "Survived {} turns, peak population {}, {} structures built, \
+                 {} tiles lost to the sea",
+                self.turns,
+                self.peak_population,
+                self.history.structures_built,
+                self.history.tiles_flooded
+            )).scale(Scale::uniform(15.0)),
+        );
+        text.set_bounds(na::Point2::new(640.0, INFINITY), Align::Center);
+        graphics::draw(
+            _ctx,
+            &text,
+            DrawParam::new().dest(na::Point2::new(0.0, 10.0)),
+        )?;
+        let graphs = [
+            (
+                "Population",
+                self.history.series(|record| record.population),
+                Color::new(0.3, 0.9, 0.3, 1.0),
+            ),
+            (
+                "Homeless",
+                self.history.series(|record| record.homeless),
+                gui::RED,
+            ),
+            (
+                "Food",
+                self.history.series(|record| record.food),
+                Color::new(0.9, 0.8, 0.2, 1.0),
+            ),
+            (
+                "Power",
+                self.history.series(|record| record.power),
+                Color::new(0.9, 0.5, 0.1, 1.0),
+            ),
+            (
+                "Nature",
+                self.history.series(|record| record.nature),
+                Color::new(0.1, 0.7, 0.3, 1.0),
+            ),
+            (
+                "Sea level",
+                self.history.series(|record| record.sealevel as i32),
+                Color::new(0.2, 0.5, 1.0, 1.0),
+            ),
+        ];
+        for (i, (label, values, color)) in graphs.iter().enumerate() {
+            gui::draw_graph(_ctx, graph_area(i), label, values, *color)?;
+        }
+        let mut text = Text::new("CLICK TO RETURN TO THE MENU");
         text.set_bounds(na::Point2::new(640.0, INFINITY), Align::Center);
         graphics::draw(
             _ctx,
             &text,
-            DrawParam::new().dest(na::Point2::new(0.0, 100.0)),
+            DrawParam::new().dest(na::Point2::new(0.0, 340.0)),
         )
     }
 
@@ -34,7 +133,7 @@ impl State for GameOver {
     ) -> GameResult<Transition> {
         _world.write_resource::<Time>().game_over = false;
         _world.write_resource::<Time>().game_over_transition_done = true;
-        Ok(Transition::Pop)
+        Ok(Transition::PopToRoot)
     }
 
     fn draw_underlying(&self) -> bool {
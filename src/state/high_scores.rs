@@ -0,0 +1,105 @@
+use super::*;
+use ggez::graphics::{DrawMode, Mesh, Rect};
+use highscores::HighScoreTable;
+use nalgebra as na;
+
+const ROW_HEIGHT: f32 = 20.0;
+
+fn row_pos(i: usize) -> na::Point2<f32> {
+    na::Point2::new(40.0, 70.0 + i as f32 * ROW_HEIGHT)
+}
+
+fn back_pos() -> na::Point2<f32> {
+    na::Point2::new(40.0, 340.0)
+}
+
+pub struct HighScores {
+    table: HighScoreTable,
+    back_text: Text,
+}
+
+impl HighScores {
+    pub fn new() -> HighScores {
+        HighScores {
+            table: HighScoreTable::default(),
+            back_text: Text::new("[back]"),
+        }
+    }
+}
+
+impl State for HighScores {
+    fn start(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+    ) -> GameResult {
+        self.table = HighScoreTable::load();
+        Ok(())
+    }
+
+    fn input(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+        _command: Command,
+        _extra: InputExtra,
+    ) -> GameResult<Transition> {
+        match _command {
+            Command::Pause | Command::Confirm => return Ok(Transition::Pop),
+            Command::Click => if gui::is_hovered(_ctx, &self.back_text, back_pos()) {
+                return Ok(Transition::Pop);
+            },
+            _ => (),
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
+        let rect = Mesh::new_rectangle(_ctx, DrawMode::Fill, Rect::new(0.0, 0.0, 640.0, 380.0))?;
+        graphics::draw(
+            _ctx,
+            &rect,
+            DrawParam::new().color(Color::new(0.0, 0.0, 0.0, 0.9)),
+        )?;
+        graphics::draw(
+            _ctx,
+            &Text::new(TextFragment::new("HIGH SCORES").scale(Scale::uniform(30.0))),
+            DrawParam::new().dest(na::Point2::new(40.0, 20.0)),
+        )?;
+        if self.table.entries.is_empty() {
+            graphics::draw(
+                _ctx,
+                &Text::new("No games finished yet."),
+                DrawParam::new().dest(row_pos(0)),
+            )?;
+        }
+        for (i, entry) in self.table.entries.iter().enumerate() {
+            graphics::draw(
+                _ctx,
+                &Text::new(format!(
+                    "{:>2}. {:<8} turn {:<4} peak pop {:<4} seed {:<10} {}",
+                    i + 1,
+                    entry.score,
+                    entry.turn,
+                    entry.peak_population,
+                    entry.seed,
+                    entry.date()
+                )),
+                DrawParam::new().dest(row_pos(i)),
+            )?;
+        }
+        gui::draw_button(_ctx, &self.back_text, back_pos())
+    }
+
+    fn draw_underlying(&self) -> bool {
+        true
+    }
+}
+
+impl Display for HighScores {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "High Scores")
+    }
+}
@@ -1,8 +1,96 @@
 use super::*;
+use nalgebra as na;
+use replay::Replay;
+use saveload::SlotInfo;
+use std::f32::INFINITY;
+use std::path::PathBuf;
 
-pub struct MainMenu;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuOption {
+    NewGame,
+    Continue,
+    Load,
+    Settings,
+    HighScores,
+    WatchReplay,
+    Quit,
+}
+
+const OPTIONS: [(MenuOption, &str); 7] = [
+    (MenuOption::NewGame, "[new game]"),
+    (MenuOption::Continue, "[continue]"),
+    (MenuOption::Load, "[load game]"),
+    (MenuOption::Settings, "[settings]"),
+    (MenuOption::HighScores, "[high scores]"),
+    (MenuOption::WatchReplay, "[watch last replay]"),
+    (MenuOption::Quit, "[quit]"),
+];
+
+fn option_pos(i: usize) -> na::Point2<f32> {
+    na::Point2::new(270.0, 170.0 + i as f32 * 25.0)
+}
 
-const MULTIPLIER: f32 = 32.0 / TILE_SIZE.0 as f32;
+pub struct MainMenu {
+    options: Vec<(MenuOption, Text)>,
+    latest: Option<SlotInfo>,
+    latest_replay: Option<PathBuf>,
+}
+
+impl MainMenu {
+    pub fn new() -> MainMenu {
+        MainMenu {
+            options: OPTIONS
+                .iter()
+                .map(|(option, label)| (*option, Text::new(*label)))
+                .collect(),
+            latest: None,
+            latest_replay: None,
+        }
+    }
+
+    fn available(&self, option: MenuOption) -> bool {
+        match option {
+            MenuOption::Continue => self.latest.is_some(),
+            MenuOption::WatchReplay => self.latest_replay.is_some(),
+            _ => true,
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.latest = saveload::list_slots().into_iter().next();
+        self.latest_replay = replay::latest();
+    }
+
+    fn choose(&mut self, world: &mut World, option: MenuOption) -> Transition {
+        match option {
+            MenuOption::NewGame => Transition::Push(Box::new(NewGame::new())),
+            MenuOption::Continue => match self.latest {
+                Some(ref latest) => match saveload::load_slot(world, &latest.name) {
+                    Ok(()) => Transition::Push(Box::new(Game::new(world))),
+                    Err(e) => {
+                        error!("Could not continue from slot {}: {:?}", latest.name, e);
+                        Transition::None
+                    }
+                },
+                None => Transition::None,
+            },
+            MenuOption::Load => Transition::Push(Box::new(SlotMenu::load(false))),
+            MenuOption::Settings => Transition::Push(Box::new(BindingsMenu::new())),
+            MenuOption::HighScores => Transition::Push(Box::new(HighScores::new())),
+            MenuOption::WatchReplay => match self.latest_replay {
+                Some(ref path) => match Replay::load(path) {
+                    Ok(replay) => Transition::Push(Box::new(ReplayViewer::new(replay))),
+                    Err(e) => {
+                        error!("Could not load replay {}: {:?}", path.display(), e);
+                        Transition::None
+                    }
+                },
+                None => Transition::None,
+            },
+            MenuOption::Quit => Transition::PopAll,
+        }
+    }
+}
 
 impl State for MainMenu {
     fn start(
@@ -14,6 +102,7 @@ impl State for MainMenu {
         info!("Main menu, go!");
         _assets.fetch_sound(SoundHandle::Waves).set_repeat(true);
         _assets.fetch_sound(SoundHandle::Waves).play();
+        self.refresh();
         Ok(())
     }
 
@@ -22,27 +111,84 @@ impl State for MainMenu {
         Ok(())
     }
 
-    fn update(
+    fn resume(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+    ) -> GameResult {
+        self.refresh();
+        Ok(())
+    }
+
+    fn input(
         &mut self,
         _ctx: &mut Context,
         _assets: &mut Assets,
         _world: &mut World,
+        _command: Command,
+        _extra: InputExtra,
     ) -> GameResult<Transition> {
-        _world.delete_all();
-        _world.maintain();
-        *_world.res.entry::<Grid>().or_insert_with(|| {
-            Grid::new(
-                (8.0 * MULTIPLIER).floor() as usize,
-                (8.0 * MULTIPLIER).floor() as usize,
-                (16.0 * MULTIPLIER).floor() as usize,
-            )
-        }) = Grid::new(
-            (8.0 * MULTIPLIER).floor() as usize,
-            (8.0 * MULTIPLIER).floor() as usize,
-            (16.0 * MULTIPLIER).floor() as usize,
-        );
-        *_world.res.entry::<Time>().or_insert_with(Time::new) = Time::new();
-        Ok(Transition::Push(Box::new(super::Game::new(_world))))
+        match _command {
+            Command::Click => {
+                let chosen = self
+                    .options
+                    .iter()
+                    .enumerate()
+                    .find(|(i, (_, text))| gui::is_hovered(_ctx, text, option_pos(*i)))
+                    .map(|(_, (option, _))| *option);
+                if let Some(option) = chosen {
+                    if self.available(option) {
+                        _assets.fetch_sound(SoundHandle::Click).play();
+                        return Ok(self.choose(_world, option));
+                    }
+                }
+            }
+            Command::Confirm => return Ok(self.choose(_world, MenuOption::NewGame)),
+            _ => (),
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
+        let mut text =
+            Text::new(TextFragment::new("IT'S NOT COOL\n\r").scale(Scale::uniform(30.0)));
+        text.set_bounds(na::Point2::new(640.0, INFINITY), Align::Center);
+        graphics::draw(
+            _ctx,
+            &text,
+            DrawParam::new().dest(na::Point2::new(0.0, 100.0)),
+        )?;
+        for (i, (option, text)) in self.options.iter().enumerate() {
+            if self.available(*option) {
+                gui::draw_button(_ctx, text, option_pos(i))?;
+            } else {
+                graphics::draw(
+                    _ctx,
+                    text,
+                    DrawParam::new()
+                        .dest(option_pos(i))
+                        .color(Color::new(0.4, 0.4, 0.4, 1.0)),
+                )?;
+            }
+        }
+        if let Some(ref latest) = self.latest {
+            let i = OPTIONS
+                .iter()
+                .position(|(option, _)| *option == MenuOption::Continue)
+                .unwrap_or(0);
+            graphics::draw(
+                _ctx,
+                &Text::new(
+                    TextFragment::new(format!("{} - turn {}", latest.name, latest.turn))
+                        .scale(Scale::uniform(15.0)),
+                ),
+                DrawParam::new()
+                    .dest(option_pos(i) + na::Vector2::new(110.0, 2.0))
+                    .color(Color::new(0.6, 0.6, 0.6, 1.0)),
+            )?;
+        }
+        Ok(())
     }
 }
 
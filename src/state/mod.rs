@@ -9,23 +9,39 @@ use assets::{random_color, Assets, MeshHandle, SoundHandle, SpriteHandle};
 use ecs::*;
 use gui;
 use input::{Command, InputExtra};
-use time::{AllThingsDoer, Time};
+use replay;
+use saveload;
+use time::{end_turn, Time};
+use undo::{self, UndoStack};
 
+mod bindings_menu;
 mod context_menu;
 mod game;
 mod game_over;
+mod high_scores;
 mod main_menu;
+mod new_game;
+mod pause_menu;
+mod replay_viewer;
+mod slot_menu;
 
+pub use self::bindings_menu::BindingsMenu;
 pub use self::context_menu::ContextMenu;
 pub use self::game::Game;
 pub use self::game_over::GameOver;
+pub use self::high_scores::HighScores;
 pub use self::main_menu::MainMenu;
+pub use self::new_game::NewGame;
+pub use self::pause_menu::PauseMenu;
+pub use self::replay_viewer::ReplayViewer;
+pub use self::slot_menu::SlotMenu;
 
 pub enum Transition {
     None,
     Push(Box<State>),
     Pop,
     PopAll,
+    PopToRoot,
     Replace(Box<State>),
 }
 
@@ -0,0 +1,241 @@
+use super::*;
+use ggez::graphics::{DrawMode, Mesh, Rect};
+use ecs::mapgen::{MAX_DIMENSIONS, MIN_DIMENSIONS, PRESETS};
+use history::History;
+use nalgebra as na;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    Preset,
+    Width,
+    Height,
+    Depth,
+}
+
+const SETTINGS: [(Setting, &str); 4] = [
+    (Setting::Preset, "Map"),
+    (Setting::Width, "Width"),
+    (Setting::Height, "Height"),
+    (Setting::Depth, "Depth"),
+];
+
+fn label_pos(i: usize) -> na::Point2<f32> {
+    na::Point2::new(160.0, 100.0 + i as f32 * 25.0)
+}
+
+fn minus_pos(i: usize) -> na::Point2<f32> {
+    na::Point2::new(280.0, 100.0 + i as f32 * 25.0)
+}
+
+fn plus_pos(i: usize) -> na::Point2<f32> {
+    na::Point2::new(310.0, 100.0 + i as f32 * 25.0)
+}
+
+fn value_pos(i: usize) -> na::Point2<f32> {
+    na::Point2::new(350.0, 100.0 + i as f32 * 25.0)
+}
+
+fn start_pos() -> na::Point2<f32> {
+    na::Point2::new(160.0, 300.0)
+}
+
+fn back_pos() -> na::Point2<f32> {
+    na::Point2::new(400.0, 300.0)
+}
+
+const MAX_SEED_DIGITS: usize = 10;
+
+fn step(value: usize, delta: i32, min: usize, max: usize) -> usize {
+    ((value as i32 + delta).max(min as i32) as usize).min(max)
+}
+
+pub struct NewGame {
+    preset: usize,
+    settings: MapSettings,
+    seed_input: String,
+    minus_text: Text,
+    plus_text: Text,
+    start_text: Text,
+    back_text: Text,
+}
+
+impl NewGame {
+    pub fn new() -> NewGame {
+        NewGame {
+            preset: 0,
+            settings: PRESETS[0].1,
+            seed_input: String::new(),
+            minus_text: Text::new("[-]"),
+            plus_text: Text::new("[+]"),
+            start_text: Text::new("[start]"),
+            back_text: Text::new("[back]"),
+        }
+    }
+
+    fn adjust(&mut self, setting: Setting, delta: i32) {
+        match setting {
+            Setting::Preset => {
+                let count = PRESETS.len() as i32;
+                self.preset = ((self.preset as i32 + delta + count) % count) as usize;
+                self.settings = PRESETS[self.preset].1;
+            }
+            Setting::Width => {
+                self.settings.width =
+                    step(self.settings.width, delta, MIN_DIMENSIONS.0, MAX_DIMENSIONS.0)
+            }
+            Setting::Height => {
+                self.settings.height =
+                    step(self.settings.height, delta, MIN_DIMENSIONS.1, MAX_DIMENSIONS.1)
+            }
+            Setting::Depth => {
+                self.settings.depth =
+                    step(self.settings.depth, delta, MIN_DIMENSIONS.2, MAX_DIMENSIONS.2)
+            }
+        }
+    }
+
+    fn value(&self, setting: Setting) -> String {
+        match setting {
+            Setting::Preset => if self.settings == PRESETS[self.preset].1 {
+                PRESETS[self.preset].0.to_owned()
+            } else {
+                format!("{} (custom)", PRESETS[self.preset].0)
+            },
+            Setting::Width => self.settings.width.to_string(),
+            Setting::Height => self.settings.height.to_string(),
+            Setting::Depth => self.settings.depth.to_string(),
+        }
+    }
+
+    fn seed(&self) -> Option<Seed> {
+        if self.seed_input.is_empty() {
+            Some(Seed::default())
+        } else {
+            self.seed_input.parse::<u32>().ok().map(Seed)
+        }
+    }
+
+    fn start_game(&mut self, world: &mut World) -> Transition {
+        let seed = match self.seed() {
+            Some(seed) => seed,
+            None => {
+                warn!("Seed {} does not fit in a u32", self.seed_input);
+                return Transition::None;
+            }
+        };
+        setup_world(world, self.settings, seed);
+        Transition::Replace(Box::new(super::Game::new(world)))
+    }
+}
+
+pub fn setup_world(world: &mut World, settings: MapSettings, seed: Seed) {
+    let (w, h, d) = settings.dimensions();
+    info!("Starting new {}x{}x{} game with seed {}", w, h, d, seed.0);
+    world.delete_all();
+    world.maintain();
+    world.add_resource(seed);
+    world.add_resource(settings);
+    world.add_resource(Grid::new(w, h, d));
+    world.add_resource(Time::new());
+    world.add_resource(History::default());
+    world.add_resource(UndoStack::default());
+    replay::start_recording(world, seed.0, settings);
+    mapgen::generate(world);
+}
+
+impl State for NewGame {
+    fn input(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+        _command: Command,
+        _extra: InputExtra,
+    ) -> GameResult<Transition> {
+        match _command {
+            Command::Text => if let InputExtra::Char(character) = _extra {
+                if character.is_ascii_digit() && self.seed_input.len() < MAX_SEED_DIGITS {
+                    self.seed_input.push(character);
+                }
+            },
+            Command::Erase => {
+                self.seed_input.pop();
+            }
+            Command::Confirm => return Ok(self.start_game(_world)),
+            Command::Pause => return Ok(Transition::Pop),
+            Command::Click => {
+                if gui::is_hovered(_ctx, &self.start_text, start_pos()) && self.seed().is_some() {
+                    _assets.fetch_sound(SoundHandle::Click).play();
+                    return Ok(self.start_game(_world));
+                }
+                if gui::is_hovered(_ctx, &self.back_text, back_pos()) {
+                    return Ok(Transition::Pop);
+                }
+                for (i, (setting, _)) in SETTINGS.iter().enumerate() {
+                    if gui::is_hovered(_ctx, &self.minus_text, minus_pos(i)) {
+                        self.adjust(*setting, -1);
+                    } else if gui::is_hovered(_ctx, &self.plus_text, plus_pos(i)) {
+                        self.adjust(*setting, 1);
+                    }
+                }
+            }
+            _ => (),
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
+        let rect = Mesh::new_rectangle(_ctx, DrawMode::Fill, Rect::new(0.0, 0.0, 640.0, 380.0))?;
+        graphics::draw(
+            _ctx,
+            &rect,
+            DrawParam::new().color(Color::new(0.0, 0.0, 0.0, 0.85)),
+        )?;
+        graphics::draw(
+            _ctx,
+            &Text::new(TextFragment::new("NEW GAME").scale(Scale::uniform(30.0))),
+            DrawParam::new().dest(na::Point2::new(160.0, 40.0)),
+        )?;
+        for (i, (setting, label)) in SETTINGS.iter().enumerate() {
+            graphics::draw(_ctx, &Text::new(*label), DrawParam::new().dest(label_pos(i)))?;
+            gui::draw_button(_ctx, &self.minus_text, minus_pos(i))?;
+            gui::draw_button(_ctx, &self.plus_text, plus_pos(i))?;
+            graphics::draw(
+                _ctx,
+                &Text::new(self.value(*setting)),
+                DrawParam::new().dest(value_pos(i)),
+            )?;
+        }
+        let i = SETTINGS.len();
+        graphics::draw(_ctx, &Text::new("Seed"), DrawParam::new().dest(label_pos(i)))?;
+        graphics::draw(
+            _ctx,
+            &Text::new(if self.seed_input.is_empty() {
+                "_ (random)".to_owned()
+            } else if self.seed().is_none() {
+                format!("{}_ (max {})", self.seed_input, u32::max_value())
+            } else {
+                format!("{}_", self.seed_input)
+            }),
+            DrawParam::new()
+                .dest(minus_pos(i))
+                .color(if self.seed().is_some() {
+                    Color::new(0.0, 0.8, 0.8, 1.0)
+                } else {
+                    gui::RED
+                }),
+        )?;
+        gui::draw_button(_ctx, &self.start_text, start_pos())?;
+        gui::draw_button(_ctx, &self.back_text, back_pos())
+    }
+
+    fn draw_underlying(&self) -> bool {
+        true
+    }
+}
+
+impl Display for NewGame {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "New Game")
+    }
+}
@@ -0,0 +1,117 @@
+use super::*;
+use ggez::graphics::{DrawMode, Mesh, Rect};
+use nalgebra as na;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseOption {
+    Resume,
+    Save,
+    Load,
+    Settings,
+    Restart,
+    QuitToMenu,
+}
+
+const OPTIONS: [(PauseOption, &str); 6] = [
+    (PauseOption::Resume, "[resume]"),
+    (PauseOption::Save, "[save game]"),
+    (PauseOption::Load, "[load game]"),
+    (PauseOption::Settings, "[settings]"),
+    (PauseOption::Restart, "[restart]"),
+    (PauseOption::QuitToMenu, "[quit to main menu]"),
+];
+
+fn option_pos(i: usize) -> na::Point2<f32> {
+    na::Point2::new(250.0, 130.0 + i as f32 * 25.0)
+}
+
+pub struct PauseMenu {
+    options: Vec<(PauseOption, Text)>,
+}
+
+impl PauseMenu {
+    pub fn new() -> PauseMenu {
+        PauseMenu {
+            options: OPTIONS
+                .iter()
+                .map(|(option, label)| (*option, Text::new(*label)))
+                .collect(),
+        }
+    }
+
+    fn choose(&mut self, world: &mut World, option: PauseOption) -> Transition {
+        match option {
+            PauseOption::Resume => Transition::Pop,
+            PauseOption::Save => Transition::Push(Box::new(SlotMenu::save())),
+            PauseOption::Load => Transition::Push(Box::new(SlotMenu::load(true))),
+            PauseOption::Settings => Transition::Push(Box::new(BindingsMenu::new())),
+            PauseOption::Restart => {
+                let seed = *world.read_resource::<Seed>();
+                let settings = *world.read_resource::<MapSettings>();
+                super::new_game::setup_world(world, settings, seed);
+                Transition::Pop
+            }
+            PauseOption::QuitToMenu => {
+                replay::save_recorded(world);
+                Transition::PopToRoot
+            }
+        }
+    }
+}
+
+impl State for PauseMenu {
+    fn input(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+        _command: Command,
+        _extra: InputExtra,
+    ) -> GameResult<Transition> {
+        match _command {
+            Command::Pause => return Ok(Transition::Pop),
+            Command::Click => {
+                let chosen = self
+                    .options
+                    .iter()
+                    .enumerate()
+                    .find(|(i, (_, text))| gui::is_hovered(_ctx, text, option_pos(*i)))
+                    .map(|(_, (option, _))| *option);
+                if let Some(option) = chosen {
+                    _assets.fetch_sound(SoundHandle::Click).play();
+                    return Ok(self.choose(_world, option));
+                }
+            }
+            _ => (),
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
+        let rect = Mesh::new_rectangle(_ctx, DrawMode::Fill, Rect::new(0.0, 0.0, 640.0, 380.0))?;
+        graphics::draw(
+            _ctx,
+            &rect,
+            DrawParam::new().color(Color::new(0.0, 0.0, 0.0, 0.6)),
+        )?;
+        graphics::draw(
+            _ctx,
+            &Text::new(TextFragment::new("PAUSED").scale(Scale::uniform(30.0))),
+            DrawParam::new().dest(na::Point2::new(250.0, 80.0)),
+        )?;
+        for (i, (_, text)) in self.options.iter().enumerate() {
+            gui::draw_button(_ctx, text, option_pos(i))?;
+        }
+        Ok(())
+    }
+
+    fn draw_underlying(&self) -> bool {
+        true
+    }
+}
+
+impl Display for PauseMenu {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "Pause Menu")
+    }
+}
@@ -0,0 +1,163 @@
+use super::*;
+use ggez::timer;
+use action::GameAction;
+use headless;
+use nalgebra as na;
+use replay::{ActionLog, Replay};
+use std::f32::INFINITY;
+
+const SPEEDS: [f32; 6] = [0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+const DEFAULT_SPEED: usize = 1;
+
+pub struct ReplayViewer {
+    replay: Replay,
+    next: usize,
+    speed: usize,
+    paused: bool,
+    elapsed: f32,
+}
+
+impl ReplayViewer {
+    pub fn new(replay: Replay) -> ReplayViewer {
+        ReplayViewer {
+            replay,
+            next: 0,
+            speed: DEFAULT_SPEED,
+            paused: false,
+            elapsed: 0.0,
+        }
+    }
+
+    fn is_finished(&self, world: &World) -> bool {
+        self.next >= self.replay.actions.len() || world.read_resource::<Time>().game_over
+    }
+
+    fn step(&mut self, world: &mut World) {
+        if self.is_finished(world) {
+            return;
+        }
+        let action = self.replay.actions[self.next];
+        debug!("Replaying action {}: {:?}", self.next, action);
+        headless::advance(world, &action);
+        world.maintain();
+        self.next += 1;
+    }
+
+    fn status(&self, world: &World) -> String {
+        let state = if self.is_finished(world) {
+            "finished"
+        } else if self.paused {
+            "paused"
+        } else {
+            "playing"
+        };
+        let last = match self.next {
+            0 => String::from("-"),
+            next => match self.replay.actions[next - 1] {
+                GameAction::Build(x, y, structure) => {
+                    format!("build {:?} at {}, {}", structure, x, y)
+                }
+                GameAction::PickUp(x, y) => format!("pick up at {}, {}", x, y),
+                GameAction::Place(x, y) => format!("place at {}, {}", x, y),
+                GameAction::SkipTurn => String::from("skip turn"),
+            },
+        };
+        format!(
+            "REPLAY {}  action {}/{}  speed x{}\n\r\
+             last: {}\n\r\
+             [enter] pause  [left/right] speed  [down] step  [esc] leave",
+            state,
+            self.next,
+            self.replay.actions.len(),
+            SPEEDS[self.speed],
+            last
+        )
+    }
+}
+
+impl State for ReplayViewer {
+    fn start(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+    ) -> GameResult {
+        info!(
+            "Replaying {} actions from seed {}",
+            self.replay.actions.len(),
+            self.replay.seed
+        );
+        super::new_game::setup_world(_world, self.replay.settings, Seed(self.replay.seed));
+        _world.add_resource(ActionLog(None));
+        _world.add_resource(Camera::default());
+        Ok(())
+    }
+
+    fn input(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+        _command: Command,
+        _extra: InputExtra,
+    ) -> GameResult<Transition> {
+        match _command {
+            Command::Pause => return Ok(Transition::Pop),
+            Command::Confirm | Command::Click => self.paused = !self.paused,
+            Command::CursorRight => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            Command::CursorLeft => self.speed = self.speed.saturating_sub(1),
+            Command::CursorDown => {
+                self.paused = true;
+                self.step(_world);
+            }
+            Command::RotateLeft => _world.write_resource::<Camera>().rotate_by(-1),
+            Command::RotateRight => _world.write_resource::<Camera>().rotate_by(1),
+            Command::ZoomIn => _world.write_resource::<Camera>().zoom_at_center(1),
+            Command::ZoomOut => _world.write_resource::<Camera>().zoom_at_center(-1),
+            _ => (),
+        }
+        Ok(Transition::None)
+    }
+
+    fn update(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+    ) -> GameResult<Transition> {
+        if self.paused || self.is_finished(_world) {
+            return Ok(Transition::None);
+        }
+        self.elapsed += timer::duration_to_f64(timer::delta(_ctx)) as f32;
+        let interval = 1.0 / SPEEDS[self.speed];
+        while self.elapsed >= interval && !self.is_finished(_world) {
+            self.elapsed -= interval;
+            self.step(_world);
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
+        super::game::draw_map(_ctx, _assets, _world)?;
+        gui::draw_score(
+            _ctx,
+            &_world.read_resource::<Time>(),
+            _world.read_resource::<Seed>().0,
+        )?;
+        let mut text = Text::new(
+            TextFragment::new(self.status(_world)).scale(Scale::uniform(15.0)),
+        );
+        text.set_bounds(na::Point2::new(640.0, INFINITY), Align::Center);
+        graphics::draw(
+            _ctx,
+            &text,
+            DrawParam::new().dest(na::Point2::new(0.0, 320.0)),
+        )
+    }
+}
+
+impl Display for ReplayViewer {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "Replay Viewer")
+    }
+}
@@ -0,0 +1,205 @@
+use super::*;
+use ggez::graphics::{DrawMode, Mesh, Rect};
+use nalgebra as na;
+use saveload::SlotInfo;
+
+const ROW_HEIGHT: f32 = 20.0;
+const MAX_ROWS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotMenuMode {
+    Save,
+    Load,
+}
+
+pub struct SlotMenu {
+    mode: SlotMenuMode,
+    in_game: bool,
+    slots: Vec<SlotInfo>,
+    name_input: String,
+    back_text: Text,
+    delete_text: Text,
+}
+
+fn row_pos(i: usize) -> na::Point2<f32> {
+    na::Point2::new(40.0, 70.0 + i as f32 * ROW_HEIGHT)
+}
+
+fn delete_pos(i: usize) -> na::Point2<f32> {
+    na::Point2::new(540.0, 70.0 + i as f32 * ROW_HEIGHT)
+}
+
+fn back_pos() -> na::Point2<f32> {
+    na::Point2::new(40.0, 340.0)
+}
+
+fn row_text(info: &SlotInfo) -> Text {
+    Text::new(format!(
+        "{:<12} turn {:<4} score {:<6} pop {:<4} seed {:<10} {}",
+        info.name,
+        info.turn,
+        info.score,
+        info.population,
+        info.seed,
+        info.date()
+    ))
+}
+
+impl SlotMenu {
+    pub fn save() -> SlotMenu {
+        SlotMenu::new(SlotMenuMode::Save, true)
+    }
+
+    pub fn load(in_game: bool) -> SlotMenu {
+        SlotMenu::new(SlotMenuMode::Load, in_game)
+    }
+
+    fn new(mode: SlotMenuMode, in_game: bool) -> SlotMenu {
+        SlotMenu {
+            mode,
+            in_game,
+            slots: Vec::new(),
+            name_input: String::new(),
+            back_text: Text::new("[back]"),
+            delete_text: Text::new("[delete]"),
+        }
+    }
+
+    fn save_to(&mut self, world: &mut World, name: &str) -> Transition {
+        match saveload::save_slot(world, name) {
+            Ok(()) => Transition::Pop,
+            Err(e) => {
+                error!("Could not save to slot {}: {:?}", name, e);
+                Transition::None
+            }
+        }
+    }
+
+    fn load_from(&mut self, world: &mut World, name: &str) -> Transition {
+        match saveload::load_slot(world, name) {
+            Ok(()) => if self.in_game {
+                Transition::Pop
+            } else {
+                Transition::Replace(Box::new(super::Game::new(world)))
+            },
+            Err(e) => {
+                error!("Could not load slot {}: {:?}", name, e);
+                Transition::None
+            }
+        }
+    }
+}
+
+impl State for SlotMenu {
+    fn start(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+    ) -> GameResult {
+        self.slots = saveload::list_slots();
+        Ok(())
+    }
+
+    fn input(
+        &mut self,
+        _ctx: &mut Context,
+        _assets: &mut Assets,
+        _world: &mut World,
+        _command: Command,
+        _extra: InputExtra,
+    ) -> GameResult<Transition> {
+        match _command {
+            Command::Text => if let InputExtra::Char(character) = _extra {
+                if self.mode == SlotMenuMode::Save && self.name_input.len() < 12 {
+                    self.name_input.push(character);
+                }
+            },
+            Command::Erase => {
+                self.name_input.pop();
+            }
+            Command::Confirm => if self.mode == SlotMenuMode::Save {
+                let name = self.name_input.clone();
+                return Ok(self.save_to(_world, &name));
+            },
+            Command::Pause => return Ok(Transition::Pop),
+            Command::Click => {
+                if gui::is_hovered(_ctx, &self.back_text, back_pos()) {
+                    return Ok(Transition::Pop);
+                }
+                let mut clicked = None;
+                for (i, info) in self.slots.iter().take(MAX_ROWS).enumerate() {
+                    if gui::is_hovered(_ctx, &self.delete_text, delete_pos(i)) {
+                        clicked = Some((info.name.clone(), true));
+                        break;
+                    }
+                    if gui::is_hovered(_ctx, &row_text(info), row_pos(i)) {
+                        clicked = Some((info.name.clone(), false));
+                        break;
+                    }
+                }
+                match clicked {
+                    Some((name, true)) => {
+                        if let Err(e) = saveload::delete_slot(&name) {
+                            error!("Could not delete slot {}: {:?}", name, e);
+                        }
+                        self.slots = saveload::list_slots();
+                    }
+                    Some((name, false)) => {
+                        _assets.fetch_sound(SoundHandle::Click).play();
+                        return Ok(match self.mode {
+                            SlotMenuMode::Save => self.save_to(_world, &name),
+                            SlotMenuMode::Load => self.load_from(_world, &name),
+                        });
+                    }
+                    None => (),
+                }
+            }
+            _ => (),
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, _assets: &mut Assets, _world: &mut World) -> GameResult {
+        let rect = Mesh::new_rectangle(_ctx, DrawMode::Fill, Rect::new(0.0, 0.0, 640.0, 380.0))?;
+        graphics::draw(
+            _ctx,
+            &rect,
+            DrawParam::new().color(Color::new(0.0, 0.0, 0.0, 0.85)),
+        )?;
+        let title = match self.mode {
+            SlotMenuMode::Save => format!("SAVE GAME\n\rNew slot: {}_", self.name_input),
+            SlotMenuMode::Load => "LOAD GAME".to_owned(),
+        };
+        graphics::draw(
+            _ctx,
+            &Text::new(title),
+            DrawParam::new().dest(na::Point2::new(40.0, 20.0)),
+        )?;
+        if self.slots.is_empty() {
+            graphics::draw(
+                _ctx,
+                &Text::new("No saved games."),
+                DrawParam::new().dest(row_pos(0)),
+            )?;
+        }
+        for (i, info) in self.slots.iter().take(MAX_ROWS).enumerate() {
+            gui::draw_button(_ctx, &row_text(info), row_pos(i))?;
+            gui::draw_button(_ctx, &self.delete_text, delete_pos(i))?;
+        }
+        gui::draw_button(_ctx, &self.back_text, back_pos())
+    }
+
+    fn draw_underlying(&self) -> bool {
+        true
+    }
+}
+
+impl Display for SlotMenu {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self.mode {
+            SlotMenuMode::Save => write!(f, "Save Menu"),
+            SlotMenuMode::Load => write!(f, "Load Menu"),
+        }
+    }
+}
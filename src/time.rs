@@ -1,6 +1,9 @@
 use ecs::*;
+use history::{History, TurnRecord};
 use specs::prelude::*;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Time {
     pub game_over: bool,
     pub game_over_transition_done: bool,
@@ -9,11 +12,13 @@ pub struct Time {
     pub turn: i32,
     pub flood_timer: i32,
     pub population: i32,
+    pub peak_population: i32,
     pub population_timer: i32,
     pub nature: i32,
     pub power: i32,
     pub homeless: i32,
     pub food: i32,
+    pub disconnected: i32,
 }
 
 impl Default for Time {
@@ -32,30 +37,64 @@ impl Time {
             turn: 0,
             flood_timer: 8,
             population: 3,
+            peak_population: 3,
             population_timer: 3,
             nature: -1,
             power: 0,
             homeless: 0,
             food: 0,
+            disconnected: 0,
         }
     }
 }
 
+fn land_columns(world: &World) -> usize {
+    let grid = world.read_resource::<Grid>();
+    let positions = world.read_storage::<Position>();
+    let tiles = world.read_storage::<Tile>();
+    (&positions, &tiles)
+        .join()
+        .filter(|(pos, tile)| **tile != Tile::Water && grid.is_top_tile(pos))
+        .count()
+}
+
+pub fn end_turn(world: &mut World) -> bool {
+    world.res.entry::<History>().or_insert_with(History::default);
+    let flooded = world.read_resource::<Time>().flood_timer < 1;
+    if flooded {
+        let before = land_columns(world);
+        mapgen::Flood.run_now(&world.res);
+        world.maintain();
+        let lost = before.saturating_sub(land_columns(world));
+        world.write_resource::<History>().tiles_flooded += lost;
+        world.write_resource::<Time>().flood_timer = 8;
+    }
+    AllThingsDoer.run_now(&world.res);
+    world.write_resource::<Time>().turn_passed = false;
+    flooded
+}
+
 pub struct AllThingsDoer;
 
 impl<'a> System<'a> for AllThingsDoer {
     type SystemData = (
         Entities<'a>,
+        Read<'a, StructureDefs>,
         Write<'a, Time>,
         Write<'a, Grid>,
+        Write<'a, History>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Tile>,
     );
 
-    fn run(&mut self, (entities, mut time, mut grid, mut positions, mut tiles): Self::SystemData) {
+    fn run(
+        &mut self,
+        (entities, defs, mut time, mut grid, mut history, mut positions, mut tiles): Self::SystemData,
+    ) {
         if time.population_timer < 1 {
             time.population += 1;
             time.population_timer = 3;
+            time.peak_population = time.peak_population.max(time.population);
         }
         if time.food < 0 && time.homeless > 0 {
             let delta = (-time.food).min(time.homeless);
@@ -65,33 +104,23 @@ impl<'a> System<'a> for AllThingsDoer {
         time.power = 0;
         time.homeless = time.population;
         time.food = -time.population;
+        time.disconnected = 0;
+        let connected = grid.main_component();
         let mut sanctuary_bonus = 0;
-        for tile in tiles.join() {
-            match tile {
-                Tile::Structure(structure) => match structure {
-                    Structure::Housing => {
-                        time.homeless -= 1;
-                        time.power -= 1;
-                    }
-                    Structure::Sanctuary => {
-                        sanctuary_bonus += 1;
-                        time.power -= 1;
-                    }
-                    Structure::Powerplant => {
-                        time.nature -= 1;
-                        time.power += 3;
-                    }
-                    Structure::Renewables => {
-                        time.power += 2;
-                    }
-                    Structure::Farm => {
-                        time.food += 2;
-                    }
-                    Structure::Fishery => {
-                        time.food += 3;
-                    }
-                },
-                _ => (),
+        let mut structures = 0;
+        for (pos, tile) in (&positions, &tiles).join() {
+            if let Tile::Structure(structure) = tile {
+                structures += 1;
+                if !connected.contains(&(pos.x(), pos.y())) {
+                    time.disconnected += 1;
+                    continue;
+                }
+                let def = defs.get(*structure);
+                time.homeless -= def.housing;
+                time.food += def.food;
+                time.power += def.power;
+                time.nature += def.nature;
+                sanctuary_bonus += def.nature_if_powered;
             }
         }
         if time.power >= 0 {
@@ -107,5 +136,8 @@ impl<'a> System<'a> for AllThingsDoer {
         if time.population == 0 {
             time.game_over = true;
         }
+        history
+            .turns
+            .push(TurnRecord::new(&time, grid.current_sealevel, structures));
     }
 }
@@ -0,0 +1,134 @@
+use specs::prelude::*;
+
+use ecs::*;
+use history::History;
+use replay::ActionLog;
+use time::Time;
+
+const MAX_UNDO: usize = 64;
+
+#[derive(Clone)]
+pub struct Snapshot {
+    grid: Grid,
+    time: Time,
+    history: History,
+    log: ActionLog,
+    tiles: Vec<(Position, Tile)>,
+}
+
+impl Snapshot {
+    pub fn take(world: &World) -> Snapshot {
+        let positions = world.read_storage::<Position>();
+        let tiles = world.read_storage::<Tile>();
+        Snapshot {
+            grid: world.read_resource::<Grid>().clone(),
+            time: world.read_resource::<Time>().clone(),
+            history: world
+                .res
+                .try_fetch::<History>()
+                .map(|history| history.clone())
+                .unwrap_or_default(),
+            log: world
+                .res
+                .try_fetch::<ActionLog>()
+                .map(|log| log.clone())
+                .unwrap_or_default(),
+            tiles: (&positions, &tiles)
+                .join()
+                .map(|(pos, tile)| (*pos, *tile))
+                .collect(),
+        }
+    }
+
+    fn restore(self, world: &mut World) {
+        world.delete_all();
+        world.maintain();
+        world.add_resource(self.grid);
+        world.add_resource(self.time);
+        world.add_resource(self.history);
+        world.add_resource(self.log);
+        {
+            let entities = world.entities();
+            let mut positions = world.write_storage::<Position>();
+            let mut tiles = world.write_storage::<Tile>();
+            for (pos, tile) in self.tiles {
+                let entity = entities.create();
+                positions.insert(entity, pos).unwrap();
+                tiles.insert(entity, tile).unwrap();
+            }
+        }
+        world.maintain();
+    }
+}
+
+#[derive(Default)]
+pub struct UndoStack {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl UndoStack {
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.undo.len() >= MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.undo.push(snapshot);
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+pub fn push(world: &mut World, snapshot: Snapshot) {
+    world
+        .res
+        .entry::<UndoStack>()
+        .or_insert_with(UndoStack::default)
+        .push(snapshot);
+}
+
+pub fn undo(world: &mut World) -> bool {
+    let current = Snapshot::take(world);
+    let previous = {
+        let mut stack = world
+            .res
+            .entry::<UndoStack>()
+            .or_insert_with(UndoStack::default);
+        match stack.undo.pop() {
+            Some(previous) => {
+                stack.redo.push(current);
+                previous
+            }
+            None => return false,
+        }
+    };
+    debug!("Undoing turn {}", world.read_resource::<Time>().turn);
+    previous.restore(world);
+    true
+}
+
+pub fn redo(world: &mut World) -> bool {
+    let current = Snapshot::take(world);
+    let next = {
+        let mut stack = world
+            .res
+            .entry::<UndoStack>()
+            .or_insert_with(UndoStack::default);
+        match stack.redo.pop() {
+            Some(next) => {
+                stack.undo.push(current);
+                next
+            }
+            None => return false,
+        }
+    };
+    debug!("Redoing turn {}", world.read_resource::<Time>().turn);
+    next.restore(world);
+    true
+}